@@ -0,0 +1,69 @@
+use ggez::{event::MouseButton, graphics, nalgebra::Point2, Context, GameResult};
+use rand::prelude::*;
+use wellspring::*;
+
+struct MainState {
+	particle_system: ParticleSystem<graphics::Text>,
+}
+
+impl MainState {
+	pub fn new() -> GameResult<Self> {
+		let text = graphics::Text::new("");
+		let mut particle_system = ParticleSystem::new(
+			text,
+			ParticleSystemSettings {
+				particle_lifetime: 1.0..1.0,
+				colors: vec![
+					graphics::Color::new(1.0, 1.0, 0.5, 1.0),
+					graphics::Color::new(1.0, 1.0, 0.5, 1.0),
+					graphics::Color::new(1.0, 0.5, 0.5, 0.0),
+				]
+				.into(),
+				sizes: vec![1.0, 2.0, 1.5, 1.5].into(),
+				speed: 50.0..75.0,
+				angle: -std::f32::consts::FRAC_PI_2,
+				spread: std::f32::consts::PI / 4.0,
+				damping: 1.0..1.0,
+				..Default::default()
+			},
+		);
+		particle_system.stop();
+		Ok(Self { particle_system })
+	}
+}
+
+impl ggez::event::EventHandler for MainState {
+	fn update(&mut self, ctx: &mut Context) -> GameResult {
+		self.particle_system.update(ctx);
+		Ok(())
+	}
+
+	fn mouse_button_down_event(
+		&mut self,
+		_ctx: &mut Context,
+		_button: MouseButton,
+		x: f32,
+		y: f32,
+	) {
+		self.particle_system.settings.position = Point2::new(x, y);
+		self.particle_system.emit_text(1, |_| {
+			let damage = thread_rng().gen_range(1, 100);
+			graphics::Text::new(damage.to_string())
+		});
+	}
+
+	fn draw(&mut self, ctx: &mut Context) -> GameResult {
+		graphics::clear(ctx, graphics::BLACK);
+		graphics::draw(ctx, &self.particle_system, graphics::DrawParam::new())?;
+		let text = graphics::Text::new("Click to deal damage");
+		graphics::draw(ctx, &text, graphics::DrawParam::new())?;
+		graphics::present(ctx)
+	}
+}
+
+fn main() -> GameResult {
+	let (mut ctx, mut event_loop) =
+		ggez::ContextBuilder::new("particle-test", "tesselode").build()?;
+	let mut main_state = MainState::new()?;
+	ggez::event::run(&mut ctx, &mut event_loop, &mut main_state)
+}
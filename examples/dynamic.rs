@@ -30,8 +30,9 @@ impl MainState {
 					graphics::Color::new(1.0, 1.0, 1.0, 1.0),
 					graphics::Color::new(1.0, 0.0, 0.0, 2.0 / 3.0),
 					graphics::Color::new(0.0, 0.0, 1.0, 0.0),
-				],
-				sizes: vec![1.0, 0.0],
+				]
+				.into(),
+				sizes: vec![1.0, 0.0].into(),
 				speed: 0.0..150.0,
 				damping: 0.1..1.0,
 				spread: 0.0,
@@ -50,16 +51,16 @@ impl MainState {
 impl ggez::event::EventHandler for MainState {
 	fn update(&mut self, ctx: &mut Context) -> GameResult {
 		if ggez::input::mouse::button_pressed(ctx, MouseButton::Left) {
-			self.particle_system.settings.colors[1] =
+			self.particle_system.settings.colors.colors_mut()[1] =
 				graphics::Color::new(0.0, 0.0, 1.0, 2.0 / 3.0);
 		} else {
-			self.particle_system.settings.colors[1] =
+			self.particle_system.settings.colors.colors_mut()[1] =
 				graphics::Color::new(1.0, 0.0, 0.0, 2.0 / 3.0);
 		}
 		if ggez::input::mouse::button_pressed(ctx, MouseButton::Right) {
-			self.particle_system.settings.sizes[0] = 2.0;
+			self.particle_system.settings.sizes.keyframes[0] = 2.0;
 		} else {
-			self.particle_system.settings.sizes[0] = 1.0;
+			self.particle_system.settings.sizes.keyframes[0] = 1.0;
 		}
 		self.particle_system.update(ctx);
 		Ok(())
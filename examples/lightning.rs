@@ -0,0 +1,77 @@
+use ggez::{
+	event::MouseButton,
+	graphics,
+	nalgebra::{Point2, Vector2},
+	Context, GameResult,
+};
+use wellspring::*;
+
+struct MainState {
+	particle_system: ParticleSystem<graphics::Mesh>,
+}
+
+impl MainState {
+	pub fn new(ctx: &mut Context) -> GameResult<Self> {
+		let mesh = graphics::Mesh::new_circle(
+			ctx,
+			graphics::DrawMode::fill(),
+			Point2::new(0.0, 0.0),
+			1.0,
+			0.1,
+			graphics::WHITE,
+		)?;
+		let mut particle_system = ParticleSystem::new(
+			mesh,
+			ParticleSystemSettings {
+				position: Point2::new(400.0, 300.0),
+				particle_lifetime: 0.2..0.3,
+				shape: EmitterShape::Rectangle(Vector2::new(30.0, 30.0), 0.0),
+				spawn_mode: SpawnMode::Chain,
+				render_mode: RenderMode::Lines(3.0),
+				colors: vec![
+					graphics::Color::new(1.0, 1.0, 1.0, 1.0),
+					graphics::Color::new(0.5, 0.5, 1.0, 0.0),
+				]
+				.into(),
+				speed: 0.0..0.0,
+				acceleration: Vector2::new(0.0, 0.0)..Vector2::new(0.0, 0.0),
+				..Default::default()
+			},
+		);
+		particle_system.stop();
+		Ok(Self { particle_system })
+	}
+}
+
+impl ggez::event::EventHandler for MainState {
+	fn update(&mut self, ctx: &mut Context) -> GameResult {
+		self.particle_system.update(ctx);
+		Ok(())
+	}
+
+	fn mouse_button_down_event(
+		&mut self,
+		_ctx: &mut Context,
+		_button: MouseButton,
+		x: f32,
+		y: f32,
+	) {
+		self.particle_system.settings.position = Point2::new(x, y);
+		self.particle_system.emit(30);
+	}
+
+	fn draw(&mut self, ctx: &mut Context) -> GameResult {
+		graphics::clear(ctx, graphics::BLACK);
+		graphics::draw(ctx, &self.particle_system, graphics::DrawParam::new())?;
+		let text = graphics::Text::new("Click to create an arc of lightning");
+		graphics::draw(ctx, &text, graphics::DrawParam::new())?;
+		graphics::present(ctx)
+	}
+}
+
+fn main() -> GameResult {
+	let (mut ctx, mut event_loop) =
+		ggez::ContextBuilder::new("particle-test", "tesselode").build()?;
+	let mut main_state = MainState::new(&mut ctx)?;
+	ggez::event::run(&mut ctx, &mut event_loop, &mut main_state)
+}
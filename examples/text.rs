@@ -21,7 +21,8 @@ impl MainState {
 				colors: vec![
 					graphics::Color::new(1.0, 1.0, 1.0, 1.0),
 					graphics::Color::new(1.0, 1.0, 1.0, 0.0),
-				],
+				]
+				.into(),
 				speed: 0.0..150.0,
 				spin: -2.0..2.0,
 				..Default::default()
@@ -0,0 +1,126 @@
+use crate::{
+	DrawableProvider, Flutter, ParticleId, ParticleSystem, ParticleSystemSettings, RandomColor,
+};
+use ggez::{
+	graphics::{self, Color},
+	nalgebra::{Point2, Vector2},
+	Context, GameResult,
+};
+
+/// A celebration effect that launches bursts of colorful confetti,
+/// built on top of a `ParticleSystem`.
+///
+/// Each piece gets a random color from a palette, falls under
+/// gravity, and flutters as it spins (see `Flutter`). The drawable
+/// should be a small, flat shape, like a rectangle.
+pub struct Confetti<D>
+where
+	D: DrawableProvider,
+{
+	system: ParticleSystem<D>,
+}
+
+impl<D> Confetti<D>
+where
+	D: DrawableProvider,
+{
+	/// Creates a confetti effect with the default palette.
+	pub fn new(drawable: D) -> Self {
+		Self::with_palette(drawable, Self::default_palette())
+	}
+
+	/// Creates a confetti effect whose pieces are colored with
+	/// colors picked at random from the palette.
+	pub fn with_palette(drawable: D, palette: Vec<Color>) -> Self {
+		let mut system = ParticleSystem::new(drawable, Self::settings());
+		system.modifiers.push(Box::new(Flutter {
+			min_drag: 0.3,
+			max_drag: 3.0,
+			lift: 2.0,
+		}));
+		system
+			.modifiers
+			.push(Box::new(RandomColor { colors: palette }));
+		Self { system }
+	}
+
+	/// Returns the settings used for confetti. The confetti shoots
+	/// upward and doesn't emit any pieces on its own.
+	pub fn settings() -> ParticleSystemSettings {
+		ParticleSystemSettings {
+			emission_rate: 0.0,
+			particle_lifetime: 3.0..5.0,
+			speed: 250.0..450.0,
+			angle: -std::f32::consts::PI / 2.0,
+			spread: 1.0,
+			acceleration: Vector2::new(0.0, 300.0)..Vector2::new(0.0, 300.0),
+			spin: -12.0..12.0,
+			opacity_over_lifetime: vec![1.0, 1.0, 1.0, 0.0].into(),
+			..Default::default()
+		}
+	}
+
+	/// Returns a palette of bright party colors.
+	pub fn default_palette() -> Vec<Color> {
+		vec![
+			Color::new(1.0, 0.3, 0.3, 1.0),
+			Color::new(1.0, 0.8, 0.2, 1.0),
+			Color::new(0.3, 0.85, 0.4, 1.0),
+			Color::new(0.3, 0.6, 1.0, 1.0),
+			Color::new(0.8, 0.4, 1.0, 1.0),
+		]
+	}
+
+	/// Returns the particle system that draws the confetti.
+	pub fn system(&self) -> &ParticleSystem<D> {
+		&self.system
+	}
+
+	/// Returns a mutable reference to the particle system that draws
+	/// the confetti, e.g. for changing its settings.
+	pub fn system_mut(&mut self) -> &mut ParticleSystem<D> {
+		&mut self.system
+	}
+
+	/// Launches the specified number of confetti pieces from a point
+	/// and returns the IDs of the new particles.
+	pub fn burst(&mut self, position: Point2<f32>, count: usize) -> Vec<ParticleId> {
+		self.system.emit_from_points((0..count).map(|_| position))
+	}
+
+	/// Returns whether all of the confetti has landed and disappeared.
+	pub fn is_finished(&self) -> bool {
+		self.system.count() == 0
+	}
+
+	/// Updates the confetti.
+	pub fn update(&mut self, ctx: &Context) {
+		self.system.update(ctx);
+	}
+
+	/// Updates the confetti by the given number of seconds.
+	pub fn advance(&mut self, delta_time: f32) {
+		self.system.advance(delta_time);
+	}
+}
+
+impl<D> graphics::Drawable for Confetti<D>
+where
+	D: DrawableProvider,
+{
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		graphics::Drawable::draw(&self.system, ctx, param)
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		None
+	}
+
+	fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		graphics::Drawable::set_blend_mode(&mut self.system, mode);
+	}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		graphics::Drawable::blend_mode(&self.system)
+	}
+}
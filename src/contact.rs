@@ -0,0 +1,126 @@
+use crate::{DrawableProvider, ParticleId, ParticleSystem, ParticleSystemSettings};
+use ggez::{
+	graphics,
+	nalgebra::{Point2, Vector2},
+	Context, GameResult,
+};
+
+/// Emits small bursts of particles at points of contact, e.g. dust
+/// kicked up by footsteps or sparks from bullet impacts, built on top
+/// of a `ParticleSystem`.
+///
+/// Each contact emits particles at the point of contact, pointing
+/// along the contact's normal. The `angle` setting (or the main
+/// emitter's angle override, if it has one) is added to the direction
+/// of the normal, and the `spread` setting spreads the particles
+/// around it as usual. The emitter doesn't emit any particles on
+/// its own.
+pub struct ContactEmitter<D>
+where
+	D: DrawableProvider,
+{
+	system: ParticleSystem<D>,
+	count: usize,
+}
+
+impl<D> ContactEmitter<D>
+where
+	D: DrawableProvider,
+{
+	/// Creates a contact emitter that emits `count` particles for
+	/// each contact.
+	pub fn new(drawable: D, settings: ParticleSystemSettings, count: usize) -> Self {
+		let mut system = ParticleSystem::new(drawable, settings);
+		system.stop();
+		Self { system, count }
+	}
+
+	/// Returns the particle system that draws the particles.
+	pub fn system(&self) -> &ParticleSystem<D> {
+		&self.system
+	}
+
+	/// Returns a mutable reference to the particle system that draws
+	/// the particles, e.g. for changing its settings.
+	pub fn system_mut(&mut self) -> &mut ParticleSystem<D> {
+		&mut self.system
+	}
+
+	/// Returns the number of particles emitted for each contact.
+	pub fn count(&self) -> usize {
+		self.count
+	}
+
+	/// Sets the number of particles emitted for each contact.
+	pub fn set_count(&mut self, count: usize) {
+		self.count = count;
+	}
+
+	/// Emits a burst of particles at a point of contact and returns
+	/// the IDs of the new particles. `normal` is the direction pointing
+	/// away from the surface that was hit, and it doesn't need to be
+	/// normalized.
+	pub fn notify_contact(
+		&mut self,
+		position: Point2<f32>,
+		normal: Vector2<f32>,
+	) -> Vec<ParticleId> {
+		self.notify_contact_with_count(position, normal, self.count)
+	}
+
+	/// Emits a burst of the specified number of particles at a point
+	/// of contact, e.g. for a harder impact, and returns the IDs of the
+	/// new particles. See `notify_contact`.
+	pub fn notify_contact_with_count(
+		&mut self,
+		position: Point2<f32>,
+		normal: Vector2<f32>,
+		count: usize,
+	) -> Vec<ParticleId> {
+		let settings = &mut self.system.settings;
+		let angle = settings.angle;
+		let angle_follows_motion = std::mem::replace(&mut settings.angle_follows_motion, false);
+		// the normal is applied through the main emitter's angle
+		// override, since that takes priority over the angle setting.
+		// An override set by the user is rotated by the normal just
+		// like the angle setting would be.
+		let overrides = &mut self.system.emitter.main_overrides;
+		let angle_override = overrides.angle;
+		overrides.angle = Some(angle_override.unwrap_or(angle) + normal.y.atan2(normal.x));
+		let ids = self.system.emit_from_points((0..count).map(|_| position));
+		self.system.emitter.main_overrides.angle = angle_override;
+		self.system.settings.angle_follows_motion = angle_follows_motion;
+		ids
+	}
+
+	/// Updates the particles.
+	pub fn update(&mut self, ctx: &Context) {
+		self.system.update(ctx);
+	}
+
+	/// Updates the particles by the given number of seconds.
+	pub fn advance(&mut self, delta_time: f32) {
+		self.system.advance(delta_time);
+	}
+}
+
+impl<D> graphics::Drawable for ContactEmitter<D>
+where
+	D: DrawableProvider,
+{
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		graphics::Drawable::draw(&self.system, ctx, param)
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		None
+	}
+
+	fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		graphics::Drawable::set_blend_mode(&mut self.system, mode);
+	}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		graphics::Drawable::blend_mode(&self.system)
+	}
+}
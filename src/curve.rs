@@ -0,0 +1,261 @@
+use crate::lerp;
+use ggez::{
+	graphics::Color,
+	nalgebra::{Point2, Vector2},
+};
+
+/// A value that can be blended with another value of the same type.
+pub trait Interpolate: Copy {
+	/// Returns a value between `self` (when `amount` is 0) and
+	/// `other` (when `amount` is 1).
+	fn interpolate(self, other: Self, amount: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+	fn interpolate(self, other: Self, amount: f32) -> Self {
+		lerp(self, other, amount)
+	}
+}
+
+impl Interpolate for Vector2<f32> {
+	fn interpolate(self, other: Self, amount: f32) -> Self {
+		lerp(self, other, amount)
+	}
+}
+
+impl Interpolate for Point2<f32> {
+	fn interpolate(self, other: Self, amount: f32) -> Self {
+		self + (other - self) * amount
+	}
+}
+
+impl Interpolate for Color {
+	fn interpolate(self, other: Self, amount: f32) -> Self {
+		Color::new(
+			lerp(self.r, other.r, amount),
+			lerp(self.g, other.g, amount),
+			lerp(self.b, other.b, amount),
+			lerp(self.a, other.a, amount),
+		)
+	}
+}
+
+/// How much `Easing::BackIn` and `Easing::BackOut` overshoot by.
+const BACK_OVERSHOOT: f32 = 1.701_58;
+
+/// How a `Curve` moves from one keyframe to the next.
+///
+/// The "in" easings start slowly, the "out" easings end slowly,
+/// and the "in-out" easings do both. The back, elastic, and bounce
+/// easings go past the keyframes, which gives effects a punchy feel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Easing {
+	/// Moves at a constant speed.
+	Linear,
+	/// Holds each keyframe until the next one is reached.
+	Step,
+	/// Starts slowly and speeds up (quadratic).
+	EaseIn,
+	/// Starts quickly and slows down (quadratic).
+	EaseOut,
+	/// Starts slowly, speeds up, and slows down again.
+	EaseInOut,
+	/// Like `EaseIn`, but more pronounced (cubic).
+	CubicIn,
+	/// Like `EaseOut`, but more pronounced (cubic).
+	CubicOut,
+	/// Like `EaseInOut`, but more pronounced (cubic).
+	CubicInOut,
+	/// Starts almost still and then shoots to the next keyframe.
+	ExpoIn,
+	/// Shoots toward the next keyframe and then settles on it.
+	ExpoOut,
+	/// Pulls back slightly before moving to the next keyframe.
+	BackIn,
+	/// Overshoots the next keyframe slightly and comes back.
+	BackOut,
+	/// Overshoots the next keyframe and wobbles around it like a spring.
+	ElasticOut,
+	/// Bounces off of the next keyframe a few times, like a dropped ball.
+	BounceOut,
+}
+
+impl Easing {
+	/// Applies the easing to a fraction from 0 to 1. Most easings
+	/// return a value from 0 to 1, but some go past the ends.
+	pub fn apply(self, amount: f32) -> f32 {
+		match self {
+			Easing::Linear => amount,
+			Easing::Step => 0.0,
+			Easing::EaseIn => amount * amount,
+			Easing::EaseOut => amount * (2.0 - amount),
+			Easing::EaseInOut => amount * amount * (3.0 - 2.0 * amount),
+			Easing::CubicIn => amount.powi(3),
+			Easing::CubicOut => 1.0 - (1.0 - amount).powi(3),
+			Easing::CubicInOut => {
+				if amount < 0.5 {
+					4.0 * amount.powi(3)
+				} else {
+					1.0 - (-2.0 * amount + 2.0).powi(3) / 2.0
+				}
+			}
+			Easing::ExpoIn => {
+				if amount <= 0.0 {
+					0.0
+				} else {
+					2.0f32.powf(10.0 * amount - 10.0)
+				}
+			}
+			Easing::ExpoOut => {
+				if amount >= 1.0 {
+					1.0
+				} else {
+					1.0 - 2.0f32.powf(-10.0 * amount)
+				}
+			}
+			Easing::BackIn => amount * amount * ((BACK_OVERSHOOT + 1.0) * amount - BACK_OVERSHOOT),
+			Easing::BackOut => 1.0 - Easing::BackIn.apply(1.0 - amount),
+			Easing::ElasticOut => {
+				if amount <= 0.0 || amount >= 1.0 {
+					amount
+				} else {
+					let period = 2.0 * std::f32::consts::PI / 3.0;
+					2.0f32.powf(-10.0 * amount) * ((amount * 10.0 - 0.75) * period).sin() + 1.0
+				}
+			}
+			Easing::BounceOut => {
+				// a ball dropped from a height of 1 that loses some
+				// of its speed every bounce
+				let (n, d) = (7.5625, 2.75);
+				if amount < 1.0 / d {
+					n * amount * amount
+				} else if amount < 2.0 / d {
+					let amount = amount - 1.5 / d;
+					n * amount * amount + 0.75
+				} else if amount < 2.5 / d {
+					let amount = amount - 2.25 / d;
+					n * amount * amount + 0.9375
+				} else {
+					let amount = amount - 2.625 / d;
+					n * amount * amount + 0.984_375
+				}
+			}
+		}
+	}
+}
+
+/// Finds the keyframes on either side of a point in time (from 0
+/// to 1) and how far between them the point is, with easing applied.
+/// Segments without an entry in `segment_easing` use `easing`.
+/// The keyframes must not be empty.
+pub(crate) fn locate_keyframes<'a, T>(
+	keyframes: &'a [T],
+	easing: Easing,
+	segment_easing: &[Option<Easing>],
+	time: f32,
+) -> (&'a T, &'a T, f32) {
+	if keyframes.len() == 1 {
+		return (&keyframes[0], &keyframes[0], 0.0);
+	}
+	let index = time.clamp(0.0, 1.0) * (keyframes.len() - 1) as f32;
+	let segment = index.floor() as usize;
+	let easing = segment_easing
+		.get(segment)
+		.copied()
+		.flatten()
+		.unwrap_or(easing);
+	(
+		&keyframes[segment],
+		&keyframes[index.ceil() as usize],
+		easing.apply(index % 1.0),
+	)
+}
+
+/// Keyframes evenly spaced from 0 to 1, e.g. for a setting that
+/// changes over the lifetime of a particle.
+///
+/// Curves are used for wellspring's own keyframe settings, and they
+/// can also be used in custom modifiers to get the same interpolation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Curve<T> {
+	/// The values of the curve, evenly spaced from 0 to 1.
+	pub keyframes: Vec<T>,
+	/// How the curve moves from one keyframe to the next.
+	pub easing: Easing,
+	/// The easing for each segment between two keyframes, for curves
+	/// that move differently in different segments. The first entry
+	/// is for the segment between the first and second keyframes, and
+	/// so on. Segments that are `None` or missing use `easing`.
+	#[cfg_attr(feature = "serialization", serde(default))]
+	pub segment_easing: Vec<Option<Easing>>,
+}
+
+impl<T> Curve<T> {
+	/// Creates a curve that moves between the keyframes at a
+	/// constant speed.
+	pub fn new(keyframes: Vec<T>) -> Self {
+		Self {
+			keyframes,
+			easing: Easing::Linear,
+			segment_easing: vec![],
+		}
+	}
+
+	/// Returns the curve with the given easing.
+	pub fn with_easing(mut self, easing: Easing) -> Self {
+		self.easing = easing;
+		self
+	}
+
+	/// Returns the curve with the given easing for one segment.
+	/// `segment` 0 is the segment between the first and second keyframes.
+	pub fn with_segment_easing(mut self, segment: usize, easing: Easing) -> Self {
+		if self.segment_easing.len() <= segment {
+			self.segment_easing.resize(segment + 1, None);
+		}
+		self.segment_easing[segment] = Some(easing);
+		self
+	}
+
+	/// Returns whether the curve has no keyframes.
+	pub fn is_empty(&self) -> bool {
+		self.keyframes.is_empty()
+	}
+}
+
+impl<T> Curve<T>
+where
+	T: Interpolate,
+{
+	/// Returns the value of the curve at a point from 0 (the first
+	/// keyframe) to 1 (the last keyframe), or `None` if the curve
+	/// has no keyframes.
+	pub fn evaluate(&self, time: f32) -> Option<T> {
+		if self.keyframes.is_empty() {
+			return None;
+		}
+		let (a, b, amount) =
+			locate_keyframes(&self.keyframes, self.easing, &self.segment_easing, time);
+		Some(a.interpolate(*b, amount))
+	}
+}
+
+impl<T> Default for Curve<T> {
+	fn default() -> Self {
+		Self::new(vec![])
+	}
+}
+
+impl<T> From<Vec<T>> for Curve<T> {
+	fn from(keyframes: Vec<T>) -> Self {
+		Self::new(keyframes)
+	}
+}
@@ -0,0 +1,183 @@
+use crate::{ParticleSystemSet, ParticleSystemSettings, SystemHandle};
+use ggez::{graphics, Context, GameResult};
+
+/// Something that happens to a particle system at a point
+/// in an effect's timeline.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum EffectEvent {
+	/// Starts the particle emitter.
+	Start,
+	/// Stops the particle emitter. Existing particles are left alone.
+	Stop,
+	/// Immediately emits the specified number of particles.
+	Burst(usize),
+	/// Changes how many particles are emitted per second.
+	SetEmissionRate(f32),
+	/// Replaces all of the particle system's settings.
+	SetSettings(Box<ParticleSystemSettings>),
+}
+
+/// An event and the time (in seconds from the start of the
+/// timeline) it happens at.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct TimedEvent {
+	/// When the event happens (in seconds).
+	pub time: f32,
+	/// What happens.
+	pub event: EffectEvent,
+}
+
+/// The events that happen to one particle system in an effect.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EffectTrack {
+	/// The particle system the events happen to.
+	pub system: SystemHandle,
+	/// The events, which don't need to be in order.
+	pub events: Vec<TimedEvent>,
+}
+
+/// A schedule of events for the particle systems in an effect.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Timeline {
+	/// The length of the timeline (in seconds).
+	pub duration: f32,
+	/// Whether the timeline starts over after reaching the end.
+	pub looping: bool,
+	/// The events for each particle system.
+	pub tracks: Vec<EffectTrack>,
+}
+
+/// A group of particle systems controlled by a timeline, e.g. a
+/// campfire that burns continuously with sparks popping out of it
+/// every few seconds.
+///
+/// Each particle system can have its own blend mode (see
+/// `ParticleSystemSet::set_blend_mode`), e.g. additive sparks over
+/// alpha-blended smoke.
+pub struct Effect {
+	/// The particle systems that make up the effect.
+	pub systems: ParticleSystemSet,
+	/// The events that happen to the particle systems over time.
+	pub timeline: Timeline,
+	time: f32,
+}
+
+impl Effect {
+	/// Creates a new effect from a set of particle systems and a
+	/// timeline. The particle systems start out however they were
+	/// when they were added to the set.
+	pub fn new(systems: ParticleSystemSet, timeline: Timeline) -> Self {
+		Self {
+			systems,
+			timeline,
+			time: 0.0,
+		}
+	}
+
+	/// Returns the current position in the timeline (in seconds).
+	pub fn time(&self) -> f32 {
+		self.time
+	}
+
+	/// Goes back to the start of the timeline. The particle
+	/// systems are left alone.
+	pub fn rewind(&mut self) {
+		self.time = 0.0;
+	}
+
+	/// Returns whether the timeline has reached the end. Looping
+	/// timelines never finish.
+	pub fn is_finished(&self) -> bool {
+		!self.timeline.looping && self.time >= self.timeline.duration
+	}
+
+	/// Runs every event that happens at or after `start` and before `end`.
+	fn run_events(&mut self, start: f32, end: f32) {
+		for track in &self.timeline.tracks {
+			let system = match self.systems.get_mut(track.system) {
+				Some(system) => system,
+				None => continue,
+			};
+			let mut events: Vec<&TimedEvent> = track
+				.events
+				.iter()
+				.filter(|event| event.time >= start && event.time < end)
+				.collect();
+			events.sort_by(|a, b| {
+				a.time
+					.partial_cmp(&b.time)
+					.unwrap_or(std::cmp::Ordering::Equal)
+			});
+			for timed_event in events {
+				match &timed_event.event {
+					EffectEvent::Start => system.start(),
+					EffectEvent::Stop => system.stop(),
+					EffectEvent::Burst(count) => {
+						system.emit(*count);
+					}
+					EffectEvent::SetEmissionRate(rate) => {
+						system.settings_mut().emission_rate = *rate;
+					}
+					EffectEvent::SetSettings(settings) => {
+						*system.settings_mut() = settings.as_ref().clone();
+					}
+				}
+			}
+		}
+	}
+
+	/// Updates the timeline and the particle systems.
+	pub fn update(&mut self, ctx: &Context) {
+		self.advance(ggez::timer::delta(ctx).as_secs_f32());
+	}
+
+	/// Updates the timeline and the particle systems by the given
+	/// number of seconds.
+	pub fn advance(&mut self, delta_time: f32) {
+		let duration = self.timeline.duration;
+		let mut start = self.time;
+		let mut end = self.time + delta_time;
+		if self.timeline.looping && duration > 0.0 {
+			while end >= duration {
+				self.run_events(start, duration);
+				start = 0.0;
+				end -= duration;
+			}
+		}
+		self.run_events(start, end);
+		self.time = end;
+		self.systems.advance(delta_time);
+	}
+}
+
+impl graphics::Drawable for Effect {
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		self.systems.draw(ctx, param)
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		None
+	}
+
+	fn set_blend_mode(&mut self, _mode: Option<graphics::BlendMode>) {}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		None
+	}
+}
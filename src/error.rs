@@ -0,0 +1,65 @@
+use ggez::GameError;
+use std::fmt;
+
+/// An error that can happen when setting up a particle system.
+#[derive(Debug)]
+pub enum WellspringError {
+	/// A setting has a value the particle system can't use. The
+	/// string describes which setting is wrong and why.
+	InvalidSettings(String),
+	/// ggez failed to create a graphics resource, e.g. the canvas
+	/// used by `ParticleSystem::enable_canvas`.
+	Graphics(GameError),
+	/// Settings couldn't be saved or loaded as RON.
+	#[cfg(feature = "serialization")]
+	Ron(ron::Error),
+	/// Settings couldn't be saved or loaded as JSON.
+	#[cfg(feature = "serialization")]
+	Json(serde_json::Error),
+}
+
+impl fmt::Display for WellspringError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WellspringError::InvalidSettings(reason) => write!(f, "invalid settings: {}", reason),
+			WellspringError::Graphics(error) => write!(f, "graphics error: {}", error),
+			#[cfg(feature = "serialization")]
+			WellspringError::Ron(error) => write!(f, "RON error: {}", error),
+			#[cfg(feature = "serialization")]
+			WellspringError::Json(error) => write!(f, "JSON error: {}", error),
+		}
+	}
+}
+
+impl std::error::Error for WellspringError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			WellspringError::InvalidSettings(_) => None,
+			WellspringError::Graphics(error) => Some(error),
+			#[cfg(feature = "serialization")]
+			WellspringError::Ron(error) => Some(error),
+			#[cfg(feature = "serialization")]
+			WellspringError::Json(error) => Some(error),
+		}
+	}
+}
+
+impl From<GameError> for WellspringError {
+	fn from(error: GameError) -> Self {
+		WellspringError::Graphics(error)
+	}
+}
+
+#[cfg(feature = "serialization")]
+impl From<ron::Error> for WellspringError {
+	fn from(error: ron::Error) -> Self {
+		WellspringError::Ron(error)
+	}
+}
+
+#[cfg(feature = "serialization")]
+impl From<serde_json::Error> for WellspringError {
+	fn from(error: serde_json::Error) -> Self {
+		WellspringError::Json(error)
+	}
+}
@@ -0,0 +1,170 @@
+use crate::{
+	curve::{locate_keyframes, Curve, Easing, Interpolate},
+	lerp,
+};
+use ggez::graphics::{self, Color};
+use std::sync::Arc;
+
+/// Converts a color component from sRGB to linear light.
+fn srgb_to_linear(value: f32) -> f32 {
+	if value <= 0.04045 {
+		value / 12.92
+	} else {
+		((value + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+/// Converts a color component from linear light to sRGB.
+fn linear_to_srgb(value: f32) -> f32 {
+	if value <= 0.003_130_8 {
+		value * 12.92
+	} else {
+		1.055 * value.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+/// The color space a `Gradient` blends between colors in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ColorSpace {
+	/// Colors are blended as they're stored. This is cheaper, but
+	/// blends between very different colors can look dark and muddy
+	/// in the middle.
+	Srgb,
+	/// Colors are blended in linear light, which keeps blends
+	/// between bright colors bright.
+	Linear,
+}
+
+/// A list of colors evenly spaced over the lifetime of a particle,
+/// stored as a `Curve`.
+///
+/// The colors are stored behind an `Arc`, so cloning a gradient is
+/// cheap, and many particle systems and presets can share the same
+/// gradient. Changing the colors of a shared gradient (using
+/// `colors_mut`) only changes that copy.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Gradient {
+	#[cfg_attr(
+		feature = "serialization",
+		serde(with = "crate::serialization::color_curve")
+	)]
+	colors: Arc<Curve<Color>>,
+	space: ColorSpace,
+}
+
+impl Gradient {
+	/// Creates a gradient that blends between the given colors in sRGB.
+	pub fn new(colors: Vec<Color>) -> Self {
+		Self {
+			colors: Arc::new(Curve::new(colors)),
+			space: ColorSpace::Srgb,
+		}
+	}
+
+	/// Returns the gradient with its colors blended in the given
+	/// color space.
+	pub fn with_space(mut self, space: ColorSpace) -> Self {
+		self.space = space;
+		self
+	}
+
+	/// Creates a gradient that starts as a solid color and fades out.
+	pub fn fade_out(color: Color) -> Self {
+		Self::new(vec![color, Color::new(color.r, color.g, color.b, 0.0)])
+	}
+
+	/// Creates a gradient for flames that starts as a bright yellow,
+	/// turns orange and then red, and fades out as dark smoke.
+	pub fn fire() -> Self {
+		Self::new(vec![
+			Color::new(1.0, 0.95, 0.6, 1.0),
+			Color::new(1.0, 0.6, 0.1, 1.0),
+			Color::new(0.8, 0.15, 0.05, 0.75),
+			Color::new(0.2, 0.2, 0.2, 0.0),
+		])
+		.with_space(ColorSpace::Linear)
+	}
+
+	/// Returns the gradient with the given easing between colors.
+	pub fn with_easing(mut self, easing: Easing) -> Self {
+		Arc::make_mut(&mut self.colors).easing = easing;
+		self
+	}
+
+	/// Returns the gradient with the given easing between two of
+	/// its colors. See `Curve::with_segment_easing`.
+	pub fn with_segment_easing(mut self, segment: usize, easing: Easing) -> Self {
+		let colors = Arc::make_mut(&mut self.colors);
+		*colors = std::mem::take(colors).with_segment_easing(segment, easing);
+		self
+	}
+
+	/// Returns the colors of the gradient.
+	pub fn colors(&self) -> &[Color] {
+		&self.colors.keyframes
+	}
+
+	/// Returns a mutable reference to the colors of the gradient. If
+	/// the colors are shared with other gradients, they're copied first.
+	pub fn colors_mut(&mut self) -> &mut Vec<Color> {
+		&mut Arc::make_mut(&mut self.colors).keyframes
+	}
+
+	/// Returns how the gradient moves from one color to the next.
+	pub fn easing(&self) -> Easing {
+		self.colors.easing
+	}
+
+	/// Returns the color space the gradient blends colors in.
+	pub fn space(&self) -> ColorSpace {
+		self.space
+	}
+
+	/// Returns the color at a point in the gradient, from 0 (the
+	/// first color) to 1 (the last color). Empty gradients are white.
+	pub fn get_color(&self, time: f32) -> Color {
+		if self.colors.is_empty() {
+			return graphics::WHITE;
+		}
+		let (color_a, color_b, fraction) = locate_keyframes(
+			&self.colors.keyframes,
+			self.colors.easing,
+			&self.colors.segment_easing,
+			time,
+		);
+		match self.space {
+			ColorSpace::Srgb => color_a.interpolate(*color_b, fraction),
+			ColorSpace::Linear => {
+				let blend = |a: f32, b: f32| {
+					linear_to_srgb(lerp(srgb_to_linear(a), srgb_to_linear(b), fraction))
+				};
+				Color::new(
+					blend(color_a.r, color_b.r),
+					blend(color_a.g, color_b.g),
+					blend(color_a.b, color_b.b),
+					lerp(color_a.a, color_b.a, fraction),
+				)
+			}
+		}
+	}
+}
+
+impl Default for Gradient {
+	fn default() -> Self {
+		Self::new(vec![graphics::WHITE])
+	}
+}
+
+impl From<Vec<Color>> for Gradient {
+	fn from(colors: Vec<Color>) -> Self {
+		Self::new(colors)
+	}
+}
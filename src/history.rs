@@ -0,0 +1,202 @@
+use crate::{
+	DrawableProvider, EmitterState, Modifier, ParticleBuffer, ParticleSystem,
+	ParticleSystemSettings,
+};
+use ggez::graphics;
+use std::collections::VecDeque;
+
+/// A copy of everything that changes when a particle system is updated.
+/// The emitter is saved whole, but only its run state is restored.
+struct Snapshot<T> {
+	time: f32,
+	settings: ParticleSystemSettings,
+	modifiers: Vec<Box<dyn Modifier>>,
+	emitter: EmitterState,
+	buffer: ParticleBuffer<T>,
+}
+
+impl<T> Snapshot<T>
+where
+	T: graphics::Drawable + Clone,
+{
+	fn new<D>(system: &ParticleSystem<D>) -> Self
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		Self {
+			time: system.emitter.clock,
+			settings: system.settings.clone(),
+			modifiers: system.modifiers.clone(),
+			emitter: system.emitter.clone(),
+			buffer: system.buffer.clone(),
+		}
+	}
+
+	fn restore<D>(&self, system: &mut ParticleSystem<D>)
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		system.settings = self.settings.clone();
+		system.modifiers = self.modifiers.clone();
+		system.emitter.restore_run_state(&self.emitter);
+		system.buffer = self.buffer.clone();
+	}
+}
+
+/// A record of a particle system's recent states, which can be used
+/// to step the particle system backwards in time, e.g. for effects
+/// that "un-explode" or for finding the moment an effect went wrong.
+///
+/// Call `record` after every update to save the particle system's
+/// state. Only the last `max_duration` seconds are kept, since every
+/// saved state is a full copy of the particles.
+///
+/// A paused particle system can also be scrubbed to any point in its
+/// history and stepped backward and forward one update at a time, e.g.
+/// in an editor while tuning an effect. Times are measured with
+/// `ParticleSystem::clock`.
+///
+/// Restoring a state brings back the particles, the emitter's timers
+/// and random number generator, and the settings and modifiers (which
+/// animations, paths, and modifiers themselves change as the system
+/// runs), as well as the extra emitters' timers and the triggers'
+/// counts. Triggers, forces, extra emitters and their overrides
+/// themselves, the camera, and the listener are left as they are.
+pub struct History<T> {
+	snapshots: VecDeque<Snapshot<T>>,
+	max_duration: f32,
+}
+
+impl<T> History<T>
+where
+	T: graphics::Drawable + Clone,
+{
+	/// Creates an empty history that keeps the last `max_duration`
+	/// seconds of states.
+	pub fn new(max_duration: f32) -> Self {
+		Self {
+			snapshots: VecDeque::new(),
+			max_duration,
+		}
+	}
+
+	/// Returns how many seconds of states are saved.
+	pub fn duration(&self) -> f32 {
+		match (self.snapshots.front(), self.snapshots.back()) {
+			(Some(first), Some(last)) => last.time - first.time,
+			_ => 0.0,
+		}
+	}
+
+	/// Removes all of the saved states.
+	pub fn clear(&mut self) {
+		self.snapshots.clear();
+	}
+
+	/// Returns the times of the oldest and newest saved states, or
+	/// `None` if no states are saved.
+	pub fn time_range(&self) -> Option<(f32, f32)> {
+		match (self.snapshots.front(), self.snapshots.back()) {
+			(Some(first), Some(last)) => Some((first.time, last.time)),
+			_ => None,
+		}
+	}
+
+	/// Saves the current state of a particle system. If the particle
+	/// system was scrubbed back in time, the states after its current
+	/// time are thrown away first.
+	pub fn record<D>(&mut self, system: &ParticleSystem<D>)
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		let snapshot = Snapshot::new(system);
+		while let Some(last) = self.snapshots.back() {
+			if last.time < snapshot.time {
+				break;
+			}
+			self.snapshots.pop_back();
+		}
+		while let Some(first) = self.snapshots.front() {
+			if snapshot.time - first.time <= self.max_duration {
+				break;
+			}
+			self.snapshots.pop_front();
+		}
+		self.snapshots.push_back(snapshot);
+	}
+
+	/// Steps a particle system back in time by the given number of
+	/// seconds, using the saved state closest to that long ago, and
+	/// returns how many seconds it actually went back. If the history
+	/// doesn't go back far enough, the oldest state is used.
+	///
+	/// States newer than the one that was restored are thrown away, so
+	/// calling this with the time since the last frame every frame plays
+	/// an effect in reverse.
+	pub fn rewind<D>(&mut self, system: &mut ParticleSystem<D>, seconds: f32) -> f32
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		let now = system.emitter.clock;
+		let index = match self.find_closest(now - seconds.max(0.0)) {
+			Some(index) => index,
+			None => return 0.0,
+		};
+		self.snapshots.truncate(index + 1);
+		self.snapshots[index].restore(system);
+		now - self.snapshots[index].time
+	}
+
+	/// Restores the saved state closest to the given time and returns
+	/// the time of that state, or `None` if no states are saved. Unlike
+	/// `rewind`, this keeps all of the saved states, so the particle
+	/// system can be scrubbed forward again.
+	pub fn scrub_to<D>(&mut self, system: &mut ParticleSystem<D>, time: f32) -> Option<f32>
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		let index = self.find_closest(time)?;
+		self.snapshots[index].restore(system);
+		Some(self.snapshots[index].time)
+	}
+
+	/// Restores the state saved before the particle system's current
+	/// state. Returns `false` if there isn't an earlier state.
+	pub fn step_back<D>(&mut self, system: &mut ParticleSystem<D>) -> bool
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		match self.find_closest(system.emitter.clock) {
+			Some(index) if index > 0 => {
+				self.snapshots[index - 1].restore(system);
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Restores the state saved after the particle system's current
+	/// state. Returns `false` if there isn't a later state.
+	pub fn step_forward<D>(&mut self, system: &mut ParticleSystem<D>) -> bool
+	where
+		D: DrawableProvider<Drawable = T>,
+	{
+		match self.find_closest(system.emitter.clock) {
+			Some(index) if index + 1 < self.snapshots.len() => {
+				self.snapshots[index + 1].restore(system);
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Returns the index of the saved state closest to the given time.
+	fn find_closest(&self, time: f32) -> Option<usize> {
+		let distance = |index: usize| (self.snapshots[index].time - time).abs();
+		(0..self.snapshots.len()).min_by(|&a, &b| {
+			distance(a)
+				.partial_cmp(&distance(b))
+				.unwrap_or(std::cmp::Ordering::Equal)
+		})
+	}
+}
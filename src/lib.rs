@@ -14,10 +14,12 @@
 //! Settings can be changed after the fact by modifying `ParticleSystem.settings`.
 //!
 //! Parameters that have a minimum and maximum value are represented
-//! using Rust's range syntax.
+//! using Rust's range syntax. The values can be in either order.
 //!
 //! # Example
 //! ```
+//! # use ggez::{graphics, nalgebra::Point2, Context, GameResult};
+//! # use wellspring::{ParticleSystem, ParticleSystemSettings};
 //! struct MainState {
 //!     particle_system: ParticleSystem<graphics::Mesh>,
 //! }
@@ -43,7 +45,8 @@
 //!                     graphics::Color::new(1.0, 1.0, 1.0, 1.0),
 //!                     graphics::Color::new(1.0, 0.0, 0.0, 2.0 / 3.0),
 //!                     graphics::Color::new(0.0, 0.0, 1.0, 0.0),
-//!                 ],
+//!                 ]
+//!                 .into(),
 //!                 speed: 100.0..200.0,
 //!                 spread: std::f32::consts::PI * 2.0,
 //!                 use_relative_angle: true,
@@ -70,6 +73,40 @@
 //!     }
 //! }
 //! ```
+mod confetti;
+mod contact;
+mod curve;
+mod effect;
+mod error;
+mod gradient;
+mod history;
+mod modifier;
+mod particle_mesh;
+mod particle_system_set;
+#[cfg(feature = "serialization")]
+mod serialization;
+mod spatial_hash;
+pub mod testing;
+mod vector_field;
+mod weather;
+
+pub use confetti::Confetti;
+pub use contact::ContactEmitter;
+pub use curve::{Curve, Easing, Interpolate};
+pub use effect::{Effect, EffectEvent, EffectTrack, TimedEvent, Timeline};
+pub use error::WellspringError;
+pub use gradient::{ColorSpace, Gradient};
+pub use history::History;
+pub use modifier::{
+	CloneModifier, ColorShift, Flutter, FreezeRegion, Modifier, Orbit, ParticleState, Pulse,
+	RandomColor, RotateVelocity, ScaleOverLifetime, SpeedByDistance, Turbulence, VelocityMode,
+	VelocityOverLifetime,
+};
+pub use particle_mesh::ParticleMesh;
+pub use particle_system_set::{ParticleSystemSet, SystemHandle};
+pub use vector_field::VectorField;
+pub use weather::{Precipitation, Weather};
+
 use ggez::{
 	graphics,
 	graphics::Color,
@@ -77,7 +114,28 @@ use ggez::{
 	Context, GameResult,
 };
 use rand::prelude::*;
-use std::ops::{Add, Mul, Range, Sub};
+use spatial_hash::{OccupancyGrid, SpatialHash};
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	ops::{Add, Mul, Range, Sub},
+	rc::Rc,
+	time::{Duration, Instant},
+};
+
+/// The alpha below which a particle is considered invisible.
+const TRANSPARENT_ALPHA_THRESHOLD: f32 = 1.0 / 512.0;
+/// How many particles `advance_budgeted` updates between checks
+/// of how much time has passed.
+const BUDGET_CHECK_INTERVAL: usize = 32;
+/// The longest time step used when warming up a particle system
+/// that just woke up.
+const WARM_UP_STEP: f32 = 1.0 / 30.0;
+/// How long a particle system runs after its longest-lived particles
+/// could have died before the `allocation_checks` feature starts
+/// checking its updates.
+#[cfg(feature = "allocation_checks")]
+const ALLOCATION_CHECK_WARM_UP: f32 = 1.0;
 
 fn lerp<T>(a: T, b: T, amount: f32) -> T
 where
@@ -86,107 +144,614 @@ where
 	a + (b - a) * amount
 }
 
-fn get_rand_in_range<T>(range: &Range<T>, rng: &mut ThreadRng) -> T
+fn get_rand_in_range<R>(range: &Range<f32>, rng: &mut R) -> f32
 where
-	T: Add<T, Output = T> + Sub<T, Output = T> + Mul<f32, Output = T> + Copy,
+	R: Rng,
+{
+	ValueRange::from(range).sample(rng.gen::<f32>())
+}
+
+/// Picks a random vector in a range, choosing the x and y
+/// components independently.
+fn get_rand_vector_in_range<R>(range: &Range<Vector2<f32>>, rng: &mut R) -> Vector2<f32>
+where
+	R: Rng,
 {
-	lerp(range.start, range.end, rng.gen::<f32>())
+	Vector2::new(
+		lerp(range.start.x, range.end.x, rng.gen::<f32>()),
+		lerp(range.start.y, range.end.y, rng.gen::<f32>()),
+	)
+}
+
+fn get_max_magnitude(range: &Range<f32>) -> f32 {
+	range.start.abs().max(range.end.abs())
+}
+
+fn get_rect_center(rect: graphics::Rect) -> Point2<f32> {
+	Point2::new(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0)
+}
+
+/// The parameters of a particle that are chosen randomly.
+#[derive(Debug, Clone, Copy)]
+enum Parameter {
+	Angle,
+	Speed,
+	Lifetime,
+	Damping,
+	Mass,
+	Acceleration,
+	RadialAcceleration,
+	TangentialAcceleration,
+	Spin,
+	/// Random changes to the particle over its lifetime.
+	Motion,
+	KillRadius,
+	Opacity,
+	SpinDamping,
+	PhaseOffset,
+}
+
+/// Copies of the keyframe settings that are shared between particles,
+/// so emitting a particle doesn't need to copy the keyframes.
+#[derive(Clone)]
+struct SharedKeyframes {
+	sizes: Rc<Curve<f32>>,
+	colors: Gradient,
+	opacity: Rc<Curve<f32>>,
+	buoyancy: Rc<Curve<f32>>,
+}
+
+impl SharedKeyframes {
+	fn new(settings: &ParticleSystemSettings) -> Self {
+		Self {
+			sizes: Rc::new(settings.sizes.clone()),
+			colors: settings.colors.clone(),
+			opacity: Rc::new(settings.opacity_over_lifetime.clone()),
+			buoyancy: Rc::new(settings.buoyancy.clone()),
+		}
+	}
+
+	fn matches(&self, settings: &ParticleSystemSettings) -> bool {
+		*self.sizes == settings.sizes
+			&& self.colors == settings.colors
+			&& *self.opacity == settings.opacity_over_lifetime
+			&& *self.buoyancy == settings.buoyancy
+	}
+}
+
+/// A small, fast random number generator (SplitMix64). Each particle
+/// has its own, so random changes to particles over time are
+/// deterministic when the particle system is seeded.
+#[derive(Debug, Clone)]
+struct ParticleRng(u64);
+
+impl ParticleRng {
+	/// Creates a random number generator for choosing one of a
+	/// particle's parameters. Each parameter gets its own stream
+	/// of random numbers, so adding randomness to one parameter
+	/// doesn't change the values chosen for the others.
+	fn for_parameter(seed: u64, parameter: Parameter) -> Self {
+		Self(seed.wrapping_add((parameter as u64 + 1).wrapping_mul(0xd1b5_4a32_d192_ed03)))
+	}
+}
+
+impl RngCore for ParticleRng {
+	fn next_u32(&mut self) -> u32 {
+		(self.next_u64() >> 32) as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		for chunk in dest.chunks_mut(8) {
+			let bytes = self.next_u64().to_le_bytes();
+			chunk.copy_from_slice(&bytes[..chunk.len()]);
+		}
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+/// Returns a random number between 0 and 1 that's always the same
+/// for a given particle seed and stream number.
+///
+/// This is useful for adding variation to particles (for example,
+/// choosing a sprite or offsetting noise) in a way that stays the same
+/// for the lifetime of each particle. Use different stream numbers to
+/// get unrelated random numbers from the same seed.
+pub fn random_from_seed(seed: u64, stream: u64) -> f32 {
+	let mut rng = ParticleRng(seed ^ stream.wrapping_mul(0x2545_f491_4f6c_dd1d));
+	rng.gen()
 }
 
-struct Particle {
+#[derive(Clone)]
+struct Particle<D> {
 	lifetime: f32,
-	sizes: Vec<f32>,
-	colors: Vec<Color>,
+	sizes: Rc<Curve<f32>>,
+	/// Multiplies the size from `sizes`, e.g. because of the particle
+	/// system's strength when the particle was emitted.
+	size_scale: f32,
+	colors: Gradient,
+	opacity: f32,
+	opacity_over_lifetime: Rc<Curve<f32>>,
+	buoyancy: Rc<Curve<f32>>,
 	use_relative_angle: bool,
+	facing: Option<Facing>,
+	emitter_position: Point2<f32>,
 	time: f32,
 	position: Point2<f32>,
+	spawn_position: Point2<f32>,
+	kill_radius: Option<f32>,
 	velocity: Vector2<f32>,
+	initial_speed: f32,
 	damping: f32,
+	terminal_velocity: Option<Vector2<f32>>,
+	mass: f32,
 	acceleration: Vector2<f32>,
 	radial_acceleration: f32,
 	tangential_acceleration: f32,
 	angle: f32,
 	spin: f32,
+	spin_damping: f32,
+	phase_offset: f32,
+	updated_at: f32,
+	brownian_motion: Option<BrownianMotion>,
+	brownian_motion_timer: f32,
 	offset: Point2<f32>,
+	scale: f32,
+	tint: Color,
+	/// How fast time passes for the particle, including the changes
+	/// made by modifiers this update.
+	time_scale: f32,
+	/// How fast time passes for the particle before modifiers run,
+	/// as set by `ParticleBuffer::set_time_scale`.
+	base_time_scale: f32,
+	seed: u64,
+	rng: ParticleRng,
+	index: usize,
+	bounces: usize,
+	max_bounces: Option<usize>,
+	sub_emitted: bool,
+	fading: bool,
+	/// The emitter that emitted the particle.
+	source: EmitterId,
+	/// Whether the particle has been through an update, so it's
+	/// had a chance to be drawn.
+	shown: bool,
+	/// Whether the particle died this update and is waiting to be
+	/// removed (see `ParticleBuffer::remove_dead`).
+	dead: bool,
+	drawable: Option<D>,
 }
 
-impl Particle {
-	fn update(&mut self, ctx: &Context, emitter_position: Point2<f32>) {
+impl<D> Particle<D>
+where
+	D: graphics::Drawable,
+{
+	fn update(
+		&mut self,
+		delta_time: f32,
+		emitter_position: Point2<f32>,
+		external_force: Vector2<f32>,
+	) {
 		let mut radial_vector = self.position - emitter_position;
 		if radial_vector.norm() != 0.0 {
 			radial_vector = radial_vector.normalize();
 		}
 		let tangential_vector = Vector2::new(-radial_vector.y, radial_vector.x);
-		let delta_time = ggez::timer::delta(ctx).as_secs_f32();
-		self.time += 1.0 / self.lifetime * delta_time;
-		self.velocity += self.acceleration * delta_time;
-		self.velocity += self.radial_acceleration * radial_vector * delta_time;
-		self.velocity += self.tangential_acceleration * tangential_vector * delta_time;
+		self.emitter_position = emitter_position;
+		if self.lifetime > 0.0 {
+			self.time += delta_time / self.lifetime;
+		} else {
+			self.time = 1.0;
+		}
+		let mut force = self.acceleration + external_force;
+		force += self.radial_acceleration * radial_vector;
+		force += self.tangential_acceleration * tangential_vector;
+		force.y -= self.get_buoyancy();
+		self.velocity += force / self.mass * delta_time;
+		if let Some(brownian_motion) = self.brownian_motion {
+			self.brownian_motion_timer += brownian_motion.frequency * delta_time;
+			while self.brownian_motion_timer >= 1.0 {
+				self.brownian_motion_timer -= 1.0;
+				let angle = 2.0 * std::f32::consts::PI * self.rng.gen::<f32>();
+				self.velocity += Vector2::new(angle.cos(), angle.sin()) * brownian_motion.strength;
+			}
+		}
 		self.velocity *= 1.0 / (1.0 + self.damping * delta_time);
+		self.spin *= 1.0 / (1.0 + self.spin_damping * delta_time);
+		if let Some(terminal_velocity) = self.terminal_velocity {
+			let (max_x, max_y) = (terminal_velocity.x.abs(), terminal_velocity.y.abs());
+			self.velocity.x = self.velocity.x.max(-max_x).min(max_x);
+			self.velocity.y = self.velocity.y.max(-max_y).min(max_y);
+		}
+	}
+
+	/// Moves and rotates the particle according to its current
+	/// velocity and spin.
+	fn integrate(&mut self, delta_time: f32) {
 		self.position += self.velocity * delta_time;
 		self.angle += self.spin * delta_time;
 	}
 
-	fn get_size(&self) -> f32 {
-		if self.sizes.len() == 1 {
-			return self.sizes[0];
+	fn fade_out(&mut self, duration: f32) {
+		let remaining_time = (1.0 - self.time) * self.lifetime;
+		if duration < remaining_time {
+			self.lifetime = duration / (1.0 - self.time);
+		}
+		self.fading = true;
+	}
+
+	/// Adds to the time the particle has left to live without moving
+	/// it through its keyframes, so its size and color don't jump.
+	fn extend_lifetime(&mut self, extra_time: f32) {
+		let remaining_time = (1.0 - self.time) * self.lifetime;
+		if remaining_time <= 0.0 {
+			return;
+		}
+		let new_remaining_time = remaining_time + extra_time;
+		if new_remaining_time <= 0.0 {
+			self.time = 1.0;
+			return;
+		}
+		self.lifetime *= new_remaining_time / remaining_time;
+	}
+
+	/// Handles the particle according to the settings'
+	/// `invalid_particle_behavior` if any of its values are NaN
+	/// or infinite.
+	#[cfg(feature = "debug_checks")]
+	fn check(&mut self, settings: &ParticleSystemSettings) {
+		let valid = self.position.x.is_finite()
+			&& self.position.y.is_finite()
+			&& self.velocity.x.is_finite()
+			&& self.velocity.y.is_finite()
+			&& self.angle.is_finite()
+			&& self.spin.is_finite()
+			// time goes past 1 in the update a particle dies, so only
+			// NaN is a problem here
+			&& !self.time.is_nan()
+			&& self.lifetime.is_finite();
+		if valid {
+			return;
+		}
+		let report = || {
+			format!(
+				"particle {} has an invalid state: {:?}\nsettings: {:#?}",
+				self.index,
+				self.view(),
+				settings
+			)
+		};
+		match settings.invalid_particle_behavior {
+			InvalidParticleBehavior::Clamp => {
+				if !(self.position.x.is_finite() && self.position.y.is_finite()) {
+					self.position = self.spawn_position;
+				}
+				self.velocity = Vector2::new(0.0, 0.0);
+				self.spin = 0.0;
+				if !self.angle.is_finite() {
+					self.angle = 0.0;
+				}
+				if self.time.is_nan() || !self.lifetime.is_finite() {
+					self.time = 1.0;
+				}
+			}
+			InvalidParticleBehavior::Report => {
+				eprintln!("wellspring: {}", report());
+				self.time = 1.0;
+			}
+			InvalidParticleBehavior::Panic => panic!("{}", report()),
+		}
+	}
+
+	fn wrap(&mut self, bounds: graphics::Rect) {
+		if bounds.w > 0.0 {
+			self.position.x = bounds.x + (self.position.x - bounds.x).rem_euclid(bounds.w);
+		}
+		if bounds.h > 0.0 {
+			self.position.y = bounds.y + (self.position.y - bounds.y).rem_euclid(bounds.h);
+		}
+	}
+
+	fn collide(&mut self, collider: &Collider) {
+		let (distance, normal) = collider.shape.get_distance(self.position);
+		if distance >= 0.0 {
+			return;
+		}
+		self.position -= normal * distance;
+		let normal_speed = self.velocity.dot(&normal);
+		if normal_speed >= 0.0 {
+			return;
+		}
+		let tangent_velocity = self.velocity - normal * normal_speed;
+		self.velocity = tangent_velocity * (1.0 - collider.friction)
+			- normal * normal_speed * collider.restitution;
+		self.bounces += 1;
+		if let Some(max_bounces) = self.max_bounces {
+			if self.bounces > max_bounces {
+				self.time = 1.0;
+			}
+		}
+	}
+
+	fn bounce(&mut self, bounds: graphics::Rect) {
+		let (left, right) = (bounds.x, bounds.x + bounds.w);
+		let (top, bottom) = (bounds.y, bounds.y + bounds.h);
+		if self.position.x < left {
+			self.position.x = left + (left - self.position.x);
+			self.velocity.x = self.velocity.x.abs();
+		} else if self.position.x > right {
+			self.position.x = right - (self.position.x - right);
+			self.velocity.x = -self.velocity.x.abs();
+		}
+		if self.position.y < top {
+			self.position.y = top + (top - self.position.y);
+			self.velocity.y = self.velocity.y.abs();
+		} else if self.position.y > bottom {
+			self.position.y = bottom - (self.position.y - bottom);
+			self.velocity.y = -self.velocity.y.abs();
+		}
+	}
+
+	/// Returns the point in the size and color keyframes (and the
+	/// drawable's animation) that the particle is at, taking its
+	/// phase offset into account.
+	fn get_animation_time(&self) -> f32 {
+		if self.phase_offset == 0.0 {
+			return self.time;
 		}
-		let size_index = self.time * (self.sizes.len() - 1) as f32;
-		let size_index_a = size_index.floor() as usize;
-		let size_index_b = size_index.ceil() as usize;
-		let size_a = self.sizes[size_index_a];
-		let size_b = self.sizes[size_index_b];
-		let fraction = size_index % 1.0;
-		lerp(size_a, size_b, fraction)
+		(self.time + self.phase_offset).rem_euclid(1.0)
+	}
+
+	fn get_size(&self) -> f32 {
+		self.sizes
+			.evaluate(self.get_animation_time())
+			.unwrap_or(1.0)
+			* self.size_scale
+			* self.scale
+	}
+
+	fn get_buoyancy(&self) -> f32 {
+		self.buoyancy.evaluate(self.time).unwrap_or(0.0)
 	}
 
 	fn get_color(&self) -> Color {
-		if self.colors.len() == 1 {
-			return self.colors[0];
-		}
-		let color_index = self.time * (self.colors.len() - 1) as f32;
-		let color_index_a = color_index.floor() as usize;
-		let color_index_b = color_index.ceil() as usize;
-		let color_a = self.colors[color_index_a];
-		let color_b = self.colors[color_index_b];
-		let fraction = color_index % 1.0;
-		return Color::new(
-			lerp(color_a.r, color_b.r, fraction),
-			lerp(color_a.g, color_b.g, fraction),
-			lerp(color_a.b, color_b.b, fraction),
-			lerp(color_a.a, color_b.a, fraction),
-		);
+		let time = self.get_animation_time();
+		let color = self.colors.get_color(time);
+		let opacity = self.opacity * self.opacity_over_lifetime.evaluate(time).unwrap_or(1.0);
+		Color::new(
+			color.r * self.tint.r,
+			color.g * self.tint.g,
+			color.b * self.tint.b,
+			color.a * self.tint.a * opacity,
+		)
 	}
 
 	fn get_angle(&self) -> f32 {
-		if self.use_relative_angle {
+		if let Some(facing) = &self.facing {
+			let direction = match facing {
+				Facing::Point(point) => point - self.position,
+				Facing::AwayFromEmitter => self.position - self.emitter_position,
+			};
+			direction.y.atan2(direction.x)
+		} else if self.use_relative_angle {
 			self.velocity.y.atan2(self.velocity.x)
 		} else {
 			self.angle
 		}
 	}
 
-	fn draw<D>(&self, ctx: &mut Context, drawable: &D) -> GameResult
-	where
-		D: graphics::Drawable,
-	{
+	fn apply_modifiers(&mut self, modifiers: &mut [Box<dyn Modifier>], delta_time: f32) {
+		let mut state = ParticleState {
+			position: self.position,
+			velocity: self.velocity,
+			angle: self.angle,
+			scale: 1.0,
+			tint: graphics::WHITE,
+			time_scale: self.base_time_scale,
+			time: self.time,
+			lifetime: self.lifetime,
+			initial_speed: self.initial_speed,
+			seed: self.seed,
+			emitter_position: self.emitter_position,
+		};
+		for modifier in modifiers {
+			modifier.apply(&mut state, delta_time);
+		}
+		self.position = state.position;
+		self.velocity = state.velocity;
+		self.angle = state.angle;
+		self.scale = state.scale;
+		self.tint = state.tint;
+		self.time_scale = state.time_scale;
+	}
+
+	fn view(&self) -> ParticleView {
+		ParticleView {
+			position: self.position,
+			velocity: self.velocity,
+			age: self.time * self.lifetime,
+			lifetime: self.lifetime,
+			size: self.get_size(),
+			color: self.get_color(),
+			rotation: self.get_angle(),
+			seed: self.seed,
+		}
+	}
+
+	/// Returns where to draw the particle if it hasn't been
+	/// simulated for `lag` seconds.
+	fn get_draw_position(&self, lag: f32) -> Point2<f32> {
+		self.position + self.velocity * lag
+	}
+
+	fn draw_param(&self, src: Option<graphics::Rect>, lag: f32) -> graphics::DrawParam {
 		let size = self.get_size();
+		let param = graphics::DrawParam::new()
+			.dest(self.get_draw_position(lag))
+			.scale(Vector2::new(size, size))
+			.rotation(self.get_angle())
+			.offset(self.offset)
+			.color(self.get_color());
+		match src {
+			Some(src) => param.src(src),
+			None => param,
+		}
+	}
+
+	fn draw(
+		&self,
+		ctx: &mut Context,
+		drawable: &D,
+		src: Option<graphics::Rect>,
+		offset: Vector2<f32>,
+		lag: f32,
+	) -> GameResult {
+		let (drawable, src) = match &self.drawable {
+			Some(drawable) => (drawable, None),
+			None => (drawable, src),
+		};
 		graphics::draw(
 			ctx,
 			drawable,
-			graphics::DrawParam::new()
-				.dest(self.position)
-				.scale(Vector2::new(size, size))
-				.rotation(self.get_angle())
-				.offset(self.offset)
-				.color(self.get_color()),
+			self.draw_param(src, lag)
+				.dest(self.get_draw_position(lag) - offset),
 		)
 	}
+
+	/// Returns whether the particle, drawn with a drawable of the given
+	/// dimensions, would overlap the bounds if it's drawn `lag` seconds
+	/// ahead of where it was simulated.
+	fn is_visible(
+		&self,
+		dimensions: Option<graphics::Rect>,
+		bounds: graphics::Rect,
+		lag: f32,
+	) -> bool {
+		let radius = match dimensions {
+			Some(dimensions) => {
+				Vector2::new(dimensions.w, dimensions.h).norm() * self.get_size().abs()
+			}
+			None => 0.0,
+		};
+		let position = self.get_draw_position(lag);
+		position.x + radius >= bounds.x
+			&& position.x - radius <= bounds.x + bounds.w
+			&& position.y + radius >= bounds.y
+			&& position.y - radius <= bounds.y + bounds.h
+	}
+}
+
+/// A unique identifier for a particle in a `ParticleSystem`.
+///
+/// IDs are never reused, so an ID for a particle that has died
+/// will never refer to a different particle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ParticleId(usize);
+
+/// A read-only snapshot of a particle's current state.
+///
+/// This is how particles are seen from outside of the particle
+/// system, e.g. when iterating over particles, querying them,
+/// or drawing them with a draw override.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleView {
+	/// The position of the particle.
+	pub position: Point2<f32>,
+	/// The velocity of the particle.
+	pub velocity: Vector2<f32>,
+	/// How long the particle has been alive (in seconds).
+	pub age: f32,
+	/// How long the particle will be alive in total (in seconds).
+	pub lifetime: f32,
+	/// The current size of the particle.
+	pub size: f32,
+	/// The current color of the particle.
+	pub color: Color,
+	/// The current angle of the particle (in radians).
+	pub rotation: f32,
+	/// A random number that stays the same for the lifetime of the particle.
+	pub seed: u64,
+}
+
+impl ParticleView {
+	/// Returns a random number between 0 and 1 that's always the same
+	/// for this particle and the given stream number. See `random_from_seed`.
+	pub fn random(&self, stream: u64) -> f32 {
+		random_from_seed(self.seed, stream)
+	}
+}
+
+/// Decides which drawable object to use for each particle.
+///
+/// Every `graphics::Drawable` is a `DrawableProvider` that uses itself
+/// for every particle, so most particle systems don't need to implement
+/// this trait. Implement it to pick a different drawable (or a different
+/// part of one) per particle, e.g. for animating particles with frames
+/// of a sprite sheet.
+pub trait DrawableProvider {
+	/// The type of object used to draw particles.
+	type Drawable: graphics::Drawable;
+
+	/// Returns the drawable object to draw a particle with, as well as
+	/// the portion of the drawable to use (see `graphics::DrawParam::src`),
+	/// if any.
+	///
+	/// - `age` is how long the particle has been alive (in seconds)
+	/// - `seed` is a random number that stays the same for the lifetime
+	///   of the particle (see `random_from_seed`)
+	/// - `index` is the number of particles the particle system emitted
+	///   before this one
+	fn drawable(
+		&self,
+		age: f32,
+		seed: u64,
+		index: usize,
+	) -> (&Self::Drawable, Option<graphics::Rect>);
+
+	/// Sets the blend mode of the drawable objects used for particles.
+	/// Providers that pick from several drawables should set the blend
+	/// mode of all of them. By default, this does nothing.
+	fn set_blend_mode(&mut self, _mode: Option<graphics::BlendMode>) {}
+}
+
+impl<D> DrawableProvider for D
+where
+	D: graphics::Drawable,
+{
+	type Drawable = D;
+
+	fn drawable(
+		&self,
+		_age: f32,
+		_seed: u64,
+		_index: usize,
+	) -> (&Self::Drawable, Option<graphics::Rect>) {
+		(self, None)
+	}
+
+	fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		graphics::Drawable::set_blend_mode(self, mode);
+	}
 }
 
 /// The length of time a particle system will keep
 /// emitting particles.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum EmitterLifetime {
 	/// The system will emit particles forever.
 	Infinite,
@@ -197,6 +762,10 @@ pub enum EmitterLifetime {
 
 /// The area in which a particle system will emit particles.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum EmitterShape {
 	/// The particle system will emit particles at a single point.
 	Point,
@@ -212,274 +781,4365 @@ pub enum EmitterShape {
 	/// The particle system will emit particles along the border
 	/// of an ellipse of the given size and rotation (in radians).
 	EllipseBorder(Vector2<f32>, f32),
+	/// The particle system will emit each particle from one of several
+	/// shapes. Shapes with higher weights are picked more often.
+	Composite(Vec<(EmitterShape, f32)>),
 }
 
-/// A configuration for a `ParticleSystem`.
-#[derive(Debug, Clone)]
-pub struct ParticleSystemSettings {
-	/// The center of the emitter.
-	pub position: Point2<f32>,
-	/// How long the emitter will keep running.
-	pub emitter_lifetime: EmitterLifetime,
-	/// How long new particles will be visible.
-	pub particle_lifetime: Range<f32>,
-	/// The number of particles the emitter produces per second.
-	pub emission_rate: f32,
-	/// The area in which the emitter spawns particles.
-	pub shape: EmitterShape,
-	/// The initial speed of new particles.
-	pub speed: Range<f32>,
-	/// The initial direction of new particles (in radians).
-	pub angle: f32,
-	/// How much the initial angle of new particles varies (in radians).
-	pub spread: f32,
-	/// The sizes of new particles over their lifetime.
-	pub sizes: Vec<f32>,
-	/// The colors of new particles over their lifetime.
-	pub colors: Vec<Color>,
-	/// The angular velocity of new particle.
-	pub spin: Range<f32>,
-	/// Whether new particles' angles should always be the same as the
-	/// direction of their movement.
-	pub use_relative_angle: bool,
-	/// The amount that new particles are slowed down each frame.
-	pub damping: Range<f32>,
-	/// The constant acceleration of new particles along the x and y axis.
-	pub acceleration: Range<Vector2<f32>>,
-	/// The acceleration of new particles relative to the center of the emitter.
-	pub radial_acceleration: Range<f32>,
-	/// The acceleration of new particles perpendicular to their current velocity.
-	pub tangential_acceleration: Range<f32>,
-	/// The offset for scaling and rotating new particles.
-	pub offset: Point2<f32>,
+impl EmitterShape {
+	/// Returns a shape that covers a rectangle, along with the point
+	/// to put the emitter at so the shape lines up with the rectangle.
+	/// The bounding box of a mesh or image can be found with
+	/// `graphics::Drawable::dimensions`.
+	pub fn from_rect(rect: graphics::Rect) -> (Self, Point2<f32>) {
+		(
+			EmitterShape::Rectangle(Vector2::new(rect.w.abs(), rect.h.abs()), 0.0),
+			get_rect_center(rect),
+		)
+	}
+
+	/// Returns a shape that covers the border of a rectangle, along
+	/// with the point to put the emitter at so the shape lines up
+	/// with the rectangle.
+	pub fn border_of(rect: graphics::Rect) -> (Self, Point2<f32>) {
+		(
+			EmitterShape::RectangleBorder(Vector2::new(rect.w.abs(), rect.h.abs()), 0.0),
+			get_rect_center(rect),
+		)
+	}
+
+	/// Returns the farthest a particle can spawn from the center
+	/// of the shape.
+	fn get_extent(&self) -> f32 {
+		match self {
+			EmitterShape::Point => 0.0,
+			EmitterShape::Composite(shapes) => shapes
+				.iter()
+				.map(|(shape, _)| shape.get_extent())
+				.fold(0.0, f32::max),
+			EmitterShape::Rectangle(size, _) | EmitterShape::RectangleBorder(size, _) => {
+				size.norm() / 2.0
+			}
+			EmitterShape::Ellipse(size, _) | EmitterShape::EllipseBorder(size, _) => {
+				size.x.abs().max(size.y.abs())
+			}
+		}
+	}
+
+	fn set_size(&mut self, new_size: Vector2<f32>) {
+		match self {
+			EmitterShape::Point | EmitterShape::Composite(_) => {}
+			EmitterShape::Rectangle(size, _)
+			| EmitterShape::Ellipse(size, _)
+			| EmitterShape::RectangleBorder(size, _)
+			| EmitterShape::EllipseBorder(size, _) => *size = new_size,
+		}
+	}
 }
 
-impl Default for ParticleSystemSettings {
-	fn default() -> Self {
-		Self {
-			position: Point2::new(0.0, 0.0),
-			emitter_lifetime: EmitterLifetime::Infinite,
-			particle_lifetime: 1.0..1.0,
-			emission_rate: 10.0,
-			shape: EmitterShape::Point,
-			speed: 10.0..100.0,
-			angle: 0.0,
-			spread: std::f32::consts::PI * 2.0,
-			sizes: vec![1.0],
-			colors: vec![graphics::WHITE],
-			spin: 0.0..0.0,
-			use_relative_angle: false,
-			damping: 0.0..0.0,
-			acceleration: Vector2::new(0.0, 0.0)..Vector2::new(0.0, 0.0),
-			radial_acceleration: 0.0..0.0,
-			tangential_acceleration: 0.0..0.0,
-			offset: Point2::new(0.5, 0.5),
+/// Keyframes for changing the emitter's settings over time, e.g. for
+/// a flamethrower that sweeps back and forth.
+///
+/// Each curve is spread over the duration of the animation. Empty
+/// curves leave the setting alone.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EmitterAnimation {
+	/// The length of the animation (in seconds since the emitter started).
+	pub duration: f32,
+	/// Keyframes for the position of the emitter.
+	pub position: Curve<Point2<f32>>,
+	/// Keyframes for the angle of the emitter.
+	pub angle: Curve<f32>,
+	/// Keyframes for the spread of the emitter.
+	pub spread: Curve<f32>,
+	/// Keyframes for the emission rate.
+	pub emission_rate: Curve<f32>,
+	/// Keyframes for the size of the emitter's shape. Points and
+	/// composite shapes aren't affected.
+	pub shape_size: Curve<Vector2<f32>>,
+}
+
+impl EmitterAnimation {
+	fn apply(&self, settings: &mut ParticleSystemSettings, time: f32) {
+		let time = if self.duration > 0.0 {
+			time / self.duration
+		} else {
+			1.0
+		};
+		if let Some(position) = self.position.evaluate(time) {
+			settings.position = position;
+		}
+		if let Some(angle) = self.angle.evaluate(time) {
+			settings.angle = angle;
+		}
+		if let Some(spread) = self.spread.evaluate(time) {
+			settings.spread = spread;
+		}
+		if let Some(emission_rate) = self.emission_rate.evaluate(time) {
+			settings.emission_rate = emission_rate;
+		}
+		if let Some(shape_size) = self.shape_size.evaluate(time) {
+			settings.shape.set_size(shape_size);
 		}
 	}
 }
 
-/// Manages and displays particles.
+/// The events that can make a `SubEmitter` emit particles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SubEmitterTrigger {
+	/// A particle is emitted.
+	Birth,
+	/// A particle reaches the end of its lifetime (or is removed
+	/// by the world bounds, kill radius, etc.).
+	Death,
+	/// Particles are emitted continuously along each particle's path
+	/// at the given rate (in emissions per second), e.g. for a trail
+	/// of sparkles behind a firework. Particles emitted into the same
+	/// system don't leave trails of their own.
+	Trail(f32),
+}
+
+/// Emits particles where other particles are born or die, e.g.
+/// for sparks that burst into smaller sparks.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct SubEmitter {
+	/// The event that makes the sub-emitter emit particles.
+	pub trigger: SubEmitterTrigger,
+	/// The number of particles to emit for each event (or each
+	/// emission of a trail).
+	pub count: usize,
+	/// The particle system in a `ParticleSystemSet` to emit particles
+	/// into. If `None`, particles are emitted into the same system.
+	/// Particles emitted into the same system don't trigger birth
+	/// sub-emitters.
+	pub target: Option<SystemHandle>,
+}
+
+/// A request from a sub-emitter to emit particles into another
+/// particle system.
+#[derive(Debug, Clone)]
+pub struct SubEmission {
+	/// The particle system to emit particles into.
+	pub target: SystemHandle,
+	/// Where to emit the particles.
+	pub position: Point2<f32>,
+	/// The number of particles to emit.
+	pub count: usize,
+}
+
+/// A range of numbers with the smaller number first.
 ///
-/// More specifically, a `ParticleSystem` is a "world" containing:
-/// - particles
-/// - an "emitter" that produces particles at a regular interval
-/// or on demand
-/// - rules governing how the particles move
-pub struct ParticleSystem<D>
-where
-	D: graphics::Drawable,
-{
-	drawable: D,
-	/// The settings defining how the particle system behaves.
-	pub settings: ParticleSystemSettings,
-	rng: ThreadRng,
-	particles: Vec<Particle>,
-	running: bool,
-	emit_timer: f32,
-	time: f32,
+/// Ranges in the settings can be written in either order, so
+/// `200.0..100.0` means the same thing as `100.0..200.0`. They're
+/// turned into `ValueRange`s before picking random values, so
+/// "the start of the range" always means the smallest value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRange {
+	/// The smallest value in the range.
+	pub min: f32,
+	/// The largest value in the range.
+	pub max: f32,
 }
 
-impl<D> ParticleSystem<D>
-where
-	D: graphics::Drawable,
-{
-	/// Creates a new particle system using the specified drawable object
-	/// to display each particle.
-	pub fn new(drawable: D, settings: ParticleSystemSettings) -> Self {
+impl ValueRange {
+	/// Creates a range between two numbers, which can be in any order.
+	pub fn new(a: f32, b: f32) -> Self {
 		Self {
-			drawable,
-			settings,
-			rng: thread_rng(),
-			particles: vec![],
-			running: true,
-			emit_timer: 1.0,
-			time: 0.0,
+			min: a.min(b),
+			max: a.max(b),
 		}
 	}
 
-	/// Returns whether the particle emitter is currently creating new particles.
-	pub fn running(&self) -> bool {
-		self.running
+	/// Returns the value `amount` of the way from the smallest value
+	/// to the largest value, where `amount` is between 0 and 1.
+	pub fn sample(&self, amount: f32) -> f32 {
+		lerp(self.min, self.max, amount)
 	}
 
-	/// Returns the number of particles in the system.
-	pub fn count(&self) -> usize {
-		self.particles.len()
+	/// Returns whether a value is in the range (including both ends).
+	pub fn contains(&self, value: f32) -> bool {
+		value >= self.min && value <= self.max
 	}
+}
 
-	/// Starts the particle emitter.
-	pub fn start(&mut self) {
+impl From<Range<f32>> for ValueRange {
+	fn from(range: Range<f32>) -> Self {
+		Self::new(range.start, range.end)
+	}
+}
+
+impl From<&Range<f32>> for ValueRange {
+	fn from(range: &Range<f32>) -> Self {
+		Self::new(range.start, range.end)
+	}
+}
+
+/// How random values are spread over a range.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Distribution {
+	/// Every value in the range is equally likely.
+	Uniform,
+	/// Values are skewed towards one end of the range. Powers above
+	/// `1.0` make values near the smallest value more common, and
+	/// powers below `1.0` make values near the largest value more
+	/// common. This is the same no matter which order the range
+	/// is written in.
+	Power(f32),
+}
+
+impl Distribution {
+	/// Reshapes a uniformly distributed random number between 0 and 1.
+	fn apply(&self, value: f32) -> f32 {
+		match self {
+			Distribution::Uniform => value,
+			Distribution::Power(power) => value.powf(*power),
+		}
+	}
+
+	/// Returns the average of the reshaped random numbers.
+	fn get_mean(&self) -> f32 {
+		match self {
+			Distribution::Uniform => 0.5,
+			Distribution::Power(power) => 1.0 / (power.max(0.0) + 1.0),
+		}
+	}
+}
+
+/// How the angles of new particles are spread over the emission cone.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SpreadDistribution {
+	/// Every angle in the cone is equally likely.
+	Uniform,
+	/// Particles are concentrated along the center of the cone, e.g.
+	/// for the core of a flamethrower. Higher strengths concentrate
+	/// them more, and a strength of `1.0` is the same as `Uniform`.
+	CenterWeighted(f32),
+	/// Particles are concentrated along the edges of the cone, e.g.
+	/// for a hollow cone. Higher strengths concentrate them more,
+	/// and a strength of `1.0` is the same as `Uniform`.
+	EdgeWeighted(f32),
+}
+
+impl SpreadDistribution {
+	/// Turns a uniformly distributed random number between 0 and 1
+	/// into an offset from the center of the cone, from -1 to 1.
+	fn apply(&self, value: f32) -> f32 {
+		let offset = value * 2.0 - 1.0;
+		let distance = match self {
+			SpreadDistribution::Uniform => return offset,
+			SpreadDistribution::CenterWeighted(strength) => offset.abs().powf(*strength),
+			SpreadDistribution::EdgeWeighted(strength) => {
+				1.0 - (1.0 - offset.abs()).powf(*strength)
+			}
+		};
+		distance.copysign(offset)
+	}
+}
+
+/// A number of particles emitted all at once at a certain time.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Burst {
+	/// The time after the emitter starts that the burst happens (in seconds).
+	pub time: f32,
+	/// The number of particles to emit.
+	pub count: usize,
+}
+
+/// A shape for a burst of particles emitted with `ParticleSystem::burst`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum BurstPattern {
+	/// Particles are spaced evenly around a circle centered on the
+	/// emitter and move outwards.
+	Ring {
+		/// The number of particles to emit.
+		count: usize,
+		/// The radius of the circle.
+		radius: f32,
+	},
+	/// Particles are emitted from the emitter's shape with their
+	/// directions spaced evenly across a cone.
+	Cone {
+		/// The number of particles to emit.
+		count: usize,
+		/// The direction the cone points in (in radians).
+		angle: f32,
+		/// The width of the cone (in radians).
+		spread: f32,
+	},
+	/// Particles are spaced evenly along a line, moving in the
+	/// directions picked by the angle and spread settings.
+	Line {
+		/// The number of particles to emit.
+		count: usize,
+		/// The start of the line.
+		from: Point2<f32>,
+		/// The end of the line.
+		to: Point2<f32>,
+	},
+}
+
+/// What happens when a particle emitter is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMode {
+	/// The emitter stops creating new particles. Existing particles
+	/// are left alone.
+	EmissionOnly,
+	/// The emitter stops creating new particles, and all existing
+	/// particles are removed.
+	ClearParticles,
+	/// The emitter stops creating particles continuously, but keeps
+	/// running until all of the scheduled bursts have happened.
+	AfterScheduledBursts,
+}
+
+/// What happens when a particle system with a maximum number of
+/// particles is full and needs to emit a new particle.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum OverflowPolicy {
+	/// The new particle isn't emitted.
+	Block,
+	/// The oldest particle is removed to make room for the new particle.
+	KillOldest,
+	/// The new particle is emitted, and the oldest particle that isn't
+	/// already fading out has its remaining lifetime shortened to at
+	/// most the given number of seconds. This lets the particle system
+	/// temporarily exceed the maximum number of particles, but particles
+	/// leave gracefully instead of suddenly disappearing.
+	FadeOldest(f32),
+}
+
+/// Settings for making particles move randomly, like dust motes
+/// or swarms of gnats.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct BrownianMotion {
+	/// How much each random push changes a particle's velocity.
+	pub strength: f32,
+	/// How many times per second particles are pushed in a random direction.
+	pub frequency: f32,
+}
+
+/// Settings for wind that pushes every particle in a system.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct WindSettings {
+	/// The average force of the wind.
+	pub base: Vector2<f32>,
+	/// How much the strength of the wind varies over time, as a
+	/// fraction of the base strength. For example, `0.5` means the
+	/// wind's strength varies between 50% and 150% of the base force.
+	pub gust_strength: f32,
+	/// How often the wind gusts (in gusts per second).
+	pub gust_frequency: f32,
+}
+
+impl WindSettings {
+	fn get_force(&self, time: f32) -> Vector2<f32> {
+		let phase = time * self.gust_frequency * 2.0 * std::f32::consts::PI;
+		// combine two waves so the gusts don't look perfectly regular
+		let gust = (phase.sin() + (phase * 2.3 + 1.7).sin()) / 2.0;
+		self.base * (1.0 + self.gust_strength * gust)
+	}
+}
+
+/// The timeline an attractor's strength keyframes are spread over.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum AttractorTimeline {
+	/// The keyframes are spread over the given number of seconds
+	/// since the emitter started.
+	Emitter(f32),
+	/// The keyframes are spread over the lifetime of each particle.
+	Particle,
+}
+
+/// A point that pulls particles towards it (or pushes them away).
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Attractor {
+	/// The position of the attractor.
+	pub position: Point2<f32>,
+	/// The strength of the attractor over time. Positive values pull
+	/// particles in, and negative values push them away. The keyframes
+	/// are evenly spaced over the timeline.
+	pub strength: Curve<f32>,
+	/// The timeline the strength keyframes are spread over.
+	pub timeline: AttractorTimeline,
+}
+
+impl Attractor {
+	fn get_force(
+		&self,
+		position: Point2<f32>,
+		particle_time: f32,
+		emitter_time: f32,
+	) -> Vector2<f32> {
+		let time = match self.timeline {
+			AttractorTimeline::Emitter(duration) if duration > 0.0 => emitter_time / duration,
+			AttractorTimeline::Emitter(_) => 1.0,
+			AttractorTimeline::Particle => particle_time,
+		};
+		let strength = match self.strength.evaluate(time) {
+			Some(strength) => strength,
+			None => return Vector2::new(0.0, 0.0),
+		};
+		let direction = self.position - position;
+		if direction.norm() == 0.0 {
+			return Vector2::new(0.0, 0.0);
+		}
+		direction.normalize() * strength
+	}
+}
+
+/// A force that only acts on particles inside a region, e.g. wind
+/// inside a chimney or a bubble that slows particles down.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ForceZone {
+	/// The area the force acts in.
+	pub region: Region,
+	/// How far inside the region (in pixels) the force reaches full
+	/// strength. The force fades in smoothly from the edge of the
+	/// region. If `0.0`, the force has a hard edge.
+	pub falloff: f32,
+	/// A constant force applied to particles in the zone.
+	pub force: Vector2<f32>,
+	/// How much particles in the zone are slowed down, proportional
+	/// to their speed.
+	pub drag: f32,
+}
+
+impl ForceZone {
+	fn get_force(&self, position: Point2<f32>, velocity: Vector2<f32>) -> Vector2<f32> {
+		let depth = self.region.get_depth(position);
+		if depth < 0.0 {
+			return Vector2::new(0.0, 0.0);
+		}
+		let amount = if self.falloff > 0.0 {
+			let amount = (depth / self.falloff).min(1.0);
+			amount * amount * (3.0 - 2.0 * amount)
+		} else {
+			1.0
+		};
+		(self.force - velocity * self.drag) * amount
+	}
+}
+
+/// A force that pushes particles depending on where they are.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Force {
+	/// The particles are pushed by the vectors in a grid.
+	VectorField(VectorField),
+	/// The particles are pulled towards (or pushed away from) a point.
+	Attractor(Attractor),
+	/// The particles are pushed or slowed down inside a region.
+	Zone(ForceZone),
+}
+
+impl Force {
+	fn set_position(&mut self, position: Point2<f32>) {
+		match self {
+			Force::VectorField(vector_field) => vector_field.position = position,
+			Force::Attractor(attractor) => attractor.position = position,
+			Force::Zone(zone) => zone.region.set_position(position),
+		}
+	}
+
+	fn get_force(
+		&self,
+		position: Point2<f32>,
+		velocity: Vector2<f32>,
+		time: f32,
+		particle_time: f32,
+		emitter_time: f32,
+	) -> Vector2<f32> {
+		match self {
+			Force::VectorField(vector_field) => vector_field.sample(position, time),
+			Force::Attractor(attractor) => {
+				attractor.get_force(position, particle_time, emitter_time)
+			}
+			Force::Zone(zone) => zone.get_force(position, velocity),
+		}
+	}
+}
+
+/// Identifies a force added with `ParticleSystem::add_force`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ForceHandle(usize);
+
+/// The shape of a `Collider`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ColliderShape {
+	/// A solid area on one side of an infinite line, such as a floor.
+	Plane {
+		/// Any point on the line.
+		point: Point2<f32>,
+		/// The direction pointing away from the solid side.
+		normal: Vector2<f32>,
+	},
+	/// A solid circle.
+	Circle { center: Point2<f32>, radius: f32 },
+}
+
+impl ColliderShape {
+	/// Returns how far a point is outside of the shape (negative if
+	/// the point is inside) and the direction out of the shape.
+	fn get_distance(&self, position: Point2<f32>) -> (f32, Vector2<f32>) {
+		match self {
+			ColliderShape::Plane { point, normal } => {
+				if normal.norm() == 0.0 {
+					return (0.0, *normal);
+				}
+				let normal = normal.normalize();
+				((position - point).dot(&normal), normal)
+			}
+			ColliderShape::Circle { center, radius } => {
+				let offset = position - center;
+				let distance = offset.norm();
+				if distance == 0.0 {
+					return (-radius, Vector2::new(0.0, -1.0));
+				}
+				(distance - radius, offset / distance)
+			}
+		}
+	}
+}
+
+/// A solid shape that particles bounce off of.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Collider {
+	/// The shape of the collider.
+	pub shape: ColliderShape,
+	/// How much of a particle's speed into the collider is kept when
+	/// it bounces. `0.0` stops the particle, and `1.0` bounces it
+	/// back at the same speed.
+	pub restitution: f32,
+	/// How much of a particle's speed along the collider is lost
+	/// when it bounces, from `0.0` to `1.0`.
+	pub friction: f32,
+}
+
+/// Settings for pushing particles away from each other.
+#[cfg(feature = "repulsion")]
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Repulsion {
+	/// The distance at which particles start pushing each other away.
+	pub radius: f32,
+	/// How strongly particles push each other away. Particles closer
+	/// to each other push each other away more strongly.
+	pub strength: f32,
+}
+
+/// Settings for keeping too many particles from piling up in
+/// one place.
+///
+/// Space is divided into a grid of square cells, and particles aren't
+/// emitted into cells that already have too many particles in them.
+/// This prevents hotspots where hundreds of identical particles are
+/// drawn on top of each other without making the effect look any
+/// different.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct DensityLimit {
+	/// The width and height of each cell.
+	pub cell_size: f32,
+	/// The maximum number of particles in each cell.
+	pub max_particles: usize,
+}
+
+/// How the strength of a particle system (see
+/// `ParticleSystem::set_strength`) changes its settings, so one value
+/// driven by gameplay (like an engine's throttle) can scale a whole
+/// effect.
+///
+/// Each curve maps the strength (from 0 to 1) to a multiplier for a
+/// setting. Empty curves leave the setting alone.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(feature = "serialization", serde(default))]
+pub struct StrengthResponse {
+	/// Multiplies the emission rate.
+	pub emission_rate: Curve<f32>,
+	/// Multiplies the initial speed of new particles.
+	pub speed: Curve<f32>,
+	/// Multiplies the sizes of new particles.
+	pub size: Curve<f32>,
+}
+
+impl StrengthResponse {
+	fn get_multiplier(curve: &Curve<f32>, strength: f32) -> f32 {
+		curve.evaluate(strength).unwrap_or(1.0)
+	}
+}
+
+/// What to do with particles whose position, velocity, angle, spin,
+/// or lifetime stop being finite numbers, which usually happens
+/// because of extreme acceleration or damping settings.
+#[cfg(feature = "debug_checks")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum InvalidParticleBehavior {
+	/// The particle is quietly fixed: it's moved back to where it was
+	/// emitted and stops moving and spinning.
+	Clamp,
+	/// The particle and the particle system's settings are printed
+	/// to stderr, and the particle is removed.
+	Report,
+	/// The particle system panics with the particle and settings.
+	Panic,
+}
+
+/// An area of the world.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Region {
+	/// The whole world.
+	All,
+	/// A rectangle.
+	Rect(
+		#[cfg_attr(feature = "serialization", serde(with = "serialization::RectDef"))]
+		graphics::Rect,
+	),
+	/// A circle.
+	Circle { center: Point2<f32>, radius: f32 },
+}
+
+impl Region {
+	fn contains(&self, point: Point2<f32>) -> bool {
+		match self {
+			Region::All => true,
+			Region::Rect(rect) => rect.contains(point),
+			Region::Circle { center, radius } => (point - center).norm() <= *radius,
+		}
+	}
+
+	/// Returns how far a point is inside of the region's edge.
+	/// Points outside of the region have a negative depth.
+	fn get_depth(&self, point: Point2<f32>) -> f32 {
+		match self {
+			Region::All => f32::INFINITY,
+			Region::Rect(rect) => (point.x - rect.x)
+				.min(rect.x + rect.w - point.x)
+				.min(point.y - rect.y)
+				.min(rect.y + rect.h - point.y),
+			Region::Circle { center, radius } => radius - (point - center).norm(),
+		}
+	}
+
+	/// Moves the region so its top-left corner (for rectangles) or
+	/// center (for circles) is at the given position.
+	fn set_position(&mut self, position: Point2<f32>) {
+		match self {
+			Region::All => {}
+			Region::Rect(rect) => rect.move_to(position),
+			Region::Circle { center, .. } => *center = position,
+		}
+	}
+}
+
+/// What happens to particles that leave the world bounds.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum BoundsBehavior {
+	/// The particle is removed.
+	Kill,
+	/// The particle wraps around to the opposite edge.
+	Wrap,
+	/// The particle bounces off of the edge.
+	Bounce,
+}
+
+/// The point new particles are spawned relative to.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SpawnMode {
+	/// New particles are spawned around the center of the emitter.
+	Emitter,
+	/// New particles are spawned around the current position of the
+	/// most recently emitted particle that is still alive, forming
+	/// a chain. If there are no particles, new particles are spawned
+	/// around the center of the emitter.
+	Chain,
+}
+
+/// A direction that particles face.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Facing {
+	/// Particles face towards a point.
+	Point(Point2<f32>),
+	/// Particles face away from the center of the emitter.
+	AwayFromEmitter,
+}
+
+/// How the colors of particles are combined with the colors of
+/// the drawable they're drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ColorMode {
+	/// The particle's color multiplies the drawable's own colors (the
+	/// vertex colors of a mesh or the pixels of an image), so white
+	/// drawables take on the particle's color exactly.
+	Multiply,
+	/// The particle's color is used instead of the drawable's own
+	/// colors. ggez always multiplies the draw color by the colors
+	/// of a drawable, so this only affects drawables whose colors
+	/// can be removed: the text given to `emit_text` has its fragment
+	/// colors cleared. Other drawables are drawn the same way as
+	/// with `Multiply`, so they should be white to be fully recolored.
+	Replace,
+}
+
+/// How a particle system displays its particles.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum RenderMode {
+	/// Each particle is drawn using the particle system's drawable.
+	Sprites,
+	/// Consecutive particles (in the order they were emitted) are
+	/// connected with line segments of the given width. The width
+	/// is scaled by the size of the particles, and each segment
+	/// uses the color of the older particle.
+	Lines(f32),
+}
+
+/// How particles are turned into lights by `ParticleSystem::light_sources`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct LightMapping {
+	/// The radius of the light from a particle with a size of 1.
+	/// The radius is multiplied by each particle's size.
+	pub radius: f32,
+	/// The brightness of each particle's light over its lifetime.
+	/// The keyframes are evenly spaced over the lifetime of each
+	/// particle and are multiplied by the particle's opacity.
+	pub intensity: Curve<f32>,
+}
+
+impl LightMapping {
+	fn get_light(&self, particle: &ParticleView) -> LightSample {
+		let time = if particle.lifetime > 0.0 {
+			particle.age / particle.lifetime
+		} else {
+			1.0
+		};
+		let intensity = self.intensity.evaluate(time).unwrap_or(1.0);
+		LightSample {
+			position: particle.position,
+			radius: self.radius * particle.size,
+			color: Color::new(particle.color.r, particle.color.g, particle.color.b, 1.0),
+			intensity: intensity * particle.color.a,
+		}
+	}
+}
+
+impl Default for LightMapping {
+	fn default() -> Self {
+		Self {
+			radius: 32.0,
+			intensity: Curve::new(vec![1.0]),
+		}
+	}
+}
+
+/// A light given off by a particle, for use with 2D lighting systems.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSample {
+	/// The position of the light.
+	pub position: Point2<f32>,
+	/// How far the light reaches.
+	pub radius: f32,
+	/// The color of the light. This is always fully opaque.
+	pub color: Color,
+	/// How bright the light is.
+	pub intensity: f32,
+}
+
+/// A configuration for a `ParticleSystem`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(feature = "serialization", serde(default))]
+pub struct ParticleSystemSettings {
+	/// The center of the emitter.
+	pub position: Point2<f32>,
+	/// Whether the emitter's position is relative to the top-left corner
+	/// of the camera passed to `ParticleSystem::update_with_camera`. When
+	/// enabled, particles that leave the camera's view wrap around to the
+	/// opposite edge, which is useful for weather effects like rain and snow.
+	pub screen_anchored: bool,
+	/// How long the emitter will keep running.
+	pub emitter_lifetime: EmitterLifetime,
+	/// How long new particles will be visible. Particles are always
+	/// drawn at least once, even if their lifetime is shorter than a
+	/// frame (or 0), e.g. for muzzle flashes.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub particle_lifetime: Range<f32>,
+	/// How new particles' lifetimes are spread over `particle_lifetime`.
+	pub lifetime_distribution: Distribution,
+	/// The number of particles the emitter produces per second.
+	pub emission_rate: f32,
+	/// How much the time between new particles varies, as a fraction
+	/// of the average time between particles. For example, `0.5` means
+	/// the time between particles can be 50% shorter or longer than
+	/// average.
+	pub emission_rate_variation: f32,
+	/// Groups of particles emitted at certain times after the
+	/// emitter starts, in addition to the emission rate.
+	pub bursts: Vec<Burst>,
+	/// A number of particles to emit as soon as the emitter starts
+	/// (or restarts).
+	pub initial_burst: Option<usize>,
+	/// Keyframes that change the emitter's settings while it's running.
+	pub animation: Option<EmitterAnimation>,
+	/// The area in which the emitter spawns particles.
+	pub shape: EmitterShape,
+	/// Whether particles spawned inside ellipses should be spread evenly
+	/// over the area. If `false`, particles are more likely to spawn near
+	/// the center of the ellipse.
+	pub uniform_area_sampling: bool,
+	/// The point the emitter shape is centered on when spawning particles.
+	pub spawn_mode: SpawnMode,
+	/// The initial speed of new particles.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub speed: Range<f32>,
+	/// The initial direction of new particles (in radians).
+	pub angle: f32,
+	/// Whether `angle` is measured from the direction the emitter is
+	/// moving in, rather than from the positive x axis. For example,
+	/// an angle of π sends exhaust out behind a moving ship. When the
+	/// emitter stops, the last direction it moved in is used.
+	pub angle_follows_motion: bool,
+	/// How much the initial angle of new particles varies (in radians).
+	pub spread: f32,
+	/// How the angles of new particles are spread over `spread`.
+	pub spread_distribution: SpreadDistribution,
+	/// The sizes of new particles over their lifetime.
+	pub sizes: Curve<f32>,
+	/// The colors of new particles over their lifetime.
+	pub colors: Gradient,
+	/// The opacity of new particles, which is multiplied by the
+	/// alpha of their colors.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub opacity: Range<f32>,
+	/// Multipliers for the opacity of new particles over their
+	/// lifetime. If this is empty, the opacity doesn't change.
+	pub opacity_over_lifetime: Curve<f32>,
+	/// How far ahead (as a fraction of their lifetime) new particles
+	/// start in their size and color keyframes and their drawable's
+	/// animation, wrapping around at the end. Giving particles
+	/// different offsets keeps dense clouds of particles from
+	/// changing in unison. This works best with keyframes and
+	/// animations that loop smoothly.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub phase_offset: Range<f32>,
+	/// The angular velocity of new particle.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub spin: Range<f32>,
+	/// The amount that the spin of new particles is slowed down
+	/// each frame, like `damping` for rotation.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub spin_damping: Range<f32>,
+	/// Random pushes applied to new particles over their lifetime.
+	pub brownian_motion: Option<BrownianMotion>,
+	/// Whether new particles' angles should always be the same as the
+	/// direction of their movement.
+	pub use_relative_angle: bool,
+	/// A direction that new particles should always face. If set,
+	/// this overrides `use_relative_angle`.
+	pub facing: Option<Facing>,
+	/// The amount that new particles are slowed down each frame.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub damping: Range<f32>,
+	/// The maximum speed of new particles along each axis. Particles
+	/// moving faster than this along an axis are slowed down to it.
+	pub terminal_velocity: Option<Vector2<f32>>,
+	/// The mass of new particles. The accelerations below are divided
+	/// by the mass, so heavier particles are affected by them less.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub mass: Range<f32>,
+	/// The constant acceleration of new particles along the x and y axis.
+	/// The x and y components are picked independently, so particles
+	/// can have any acceleration in the rectangle between the start
+	/// and end of the range.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub acceleration: Range<Vector2<f32>>,
+	/// The acceleration of new particles relative to the center of the emitter.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub radial_acceleration: Range<f32>,
+	/// The acceleration of new particles perpendicular to their current velocity.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::range"))]
+	pub tangential_acceleration: Range<f32>,
+	/// The upward acceleration of new particles over their lifetime.
+	/// This is useful for making smoke rise quickly at first and
+	/// then slow down as it cools off.
+	pub buoyancy: Curve<f32>,
+	/// The offset for scaling and rotating new particles.
+	pub offset: Point2<f32>,
+	/// Wind that pushes all particles, including existing ones. Like
+	/// the accelerations, the wind's force is divided by each particle's mass.
+	pub wind: Option<WindSettings>,
+	/// Forces that push all particles, including existing ones. Like
+	/// the accelerations, forces are divided by each particle's mass.
+	pub forces: Vec<Force>,
+	/// Solid shapes that all particles bounce off of.
+	pub colliders: Vec<Collider>,
+	/// Emitters that create particles when other particles are
+	/// born or die.
+	pub sub_emitters: Vec<SubEmitter>,
+	/// The number of times new particles can bounce off of colliders.
+	/// Particles are removed when they hit a collider after bouncing
+	/// this many times.
+	pub max_bounces: Option<usize>,
+	/// The maximum number of particles the system can have at once.
+	pub max_particles: Option<usize>,
+	/// What happens when the system has the maximum number of particles
+	/// and needs to emit a new one.
+	pub overflow_policy: OverflowPolicy,
+	/// How the particles are displayed.
+	pub render_mode: RenderMode,
+	/// Whether the colors of the particles multiply or replace the
+	/// colors of the drawable they're drawn with.
+	pub color_mode: ColorMode,
+	/// The area particles are kept within, and what happens to
+	/// particles that leave it.
+	#[cfg_attr(feature = "serialization", serde(with = "serialization::world_bounds"))]
+	pub world_bounds: Option<(graphics::Rect, BoundsBehavior)>,
+	/// The size of the cells used to speed up `ParticleSystem::query_rect`
+	/// and `ParticleSystem::query_circle`. Ideally, this should be a bit
+	/// larger than the areas that are usually queried. If `None`, queries
+	/// check every particle.
+	pub query_cell_size: Option<f32>,
+	/// Whether particles push away from each other when they're close,
+	/// which keeps dense clouds of particles from stacking up.
+	#[cfg(feature = "repulsion")]
+	pub repulsion: Option<Repulsion>,
+	/// What to do with particles that end up with NaN or infinite
+	/// values, which are checked for after every update.
+	#[cfg(feature = "debug_checks")]
+	pub invalid_particle_behavior: InvalidParticleBehavior,
+	/// Whether particles should be removed as soon as they become fully
+	/// transparent, even if their lifetime isn't over. This saves time
+	/// for effects that spend a while fading out, but it shouldn't be
+	/// used if the colors become opaque again after being transparent.
+	pub kill_when_transparent: bool,
+	/// The distance from their spawn point at which new particles are
+	/// removed, even if their lifetime isn't over. This keeps the
+	/// outer edge of ring-shaped effects crisp.
+	#[cfg_attr(
+		feature = "serialization",
+		serde(with = "serialization::optional_range")
+	)]
+	pub kill_radius: Option<Range<f32>>,
+	/// How particles are turned into lights by `light_sources`.
+	pub light_mapping: LightMapping,
+	/// How often the particles are simulated (in seconds), or `None`
+	/// to simulate them every update. Between simulation steps, the
+	/// particles are drawn where they would be if they kept moving at
+	/// the same velocity. Simulating far away or background effects
+	/// less often (e.g. `Some(1.0 / 15.0)`) saves a lot of time in
+	/// scenes with many of them.
+	pub update_interval: Option<f32>,
+	/// How close the listener (see `ParticleSystem::set_listener`) has
+	/// to be to one of the emitters for the particle system to be
+	/// updated, or `None` to always update it. Far away particle systems
+	/// go to sleep: their particles are frozen in place and no new
+	/// particles are emitted until the listener comes back in range.
+	pub activation_radius: Option<f32>,
+	/// How many seconds the particle system is simulated for at once
+	/// when it wakes up (see `activation_radius`), so effects like
+	/// smoke and fire look like they were running the whole time.
+	pub wake_warm_up: f32,
+	/// Limits how many particles can be in the same area. Particles
+	/// that would be emitted into a crowded area are skipped.
+	pub density_limit: Option<DensityLimit>,
+	/// How the particle system's strength changes the emission rate
+	/// and the speeds and sizes of new particles.
+	pub strength_response: StrengthResponse,
+}
+
+impl Default for ParticleSystemSettings {
+	fn default() -> Self {
+		Self {
+			position: Point2::new(0.0, 0.0),
+			screen_anchored: false,
+			emitter_lifetime: EmitterLifetime::Infinite,
+			particle_lifetime: 1.0..1.0,
+			lifetime_distribution: Distribution::Uniform,
+			emission_rate: 10.0,
+			emission_rate_variation: 0.0,
+			bursts: vec![],
+			initial_burst: None,
+			animation: None,
+			shape: EmitterShape::Point,
+			uniform_area_sampling: false,
+			spawn_mode: SpawnMode::Emitter,
+			speed: 10.0..100.0,
+			angle: 0.0,
+			angle_follows_motion: false,
+			spread: std::f32::consts::PI * 2.0,
+			spread_distribution: SpreadDistribution::Uniform,
+			sizes: Curve::new(vec![1.0]),
+			colors: Gradient::default(),
+			opacity: 1.0..1.0,
+			opacity_over_lifetime: Curve::default(),
+			phase_offset: 0.0..0.0,
+			spin: 0.0..0.0,
+			spin_damping: 0.0..0.0,
+			brownian_motion: None,
+			use_relative_angle: false,
+			facing: None,
+			damping: 0.0..0.0,
+			terminal_velocity: None,
+			mass: 1.0..1.0,
+			acceleration: Vector2::new(0.0, 0.0)..Vector2::new(0.0, 0.0),
+			radial_acceleration: 0.0..0.0,
+			tangential_acceleration: 0.0..0.0,
+			buoyancy: Curve::default(),
+			offset: Point2::new(0.5, 0.5),
+			wind: None,
+			forces: vec![],
+			colliders: vec![],
+			sub_emitters: vec![],
+			max_bounces: None,
+			max_particles: None,
+			overflow_policy: OverflowPolicy::Block,
+			render_mode: RenderMode::Sprites,
+			color_mode: ColorMode::Multiply,
+			world_bounds: None,
+			query_cell_size: None,
+			#[cfg(feature = "repulsion")]
+			repulsion: None,
+			#[cfg(feature = "debug_checks")]
+			invalid_particle_behavior: InvalidParticleBehavior::Report,
+			kill_when_transparent: false,
+			kill_radius: None,
+			light_mapping: LightMapping::default(),
+			update_interval: None,
+			activation_radius: None,
+			wake_warm_up: 0.0,
+			density_limit: None,
+			strength_response: StrengthResponse::default(),
+		}
+	}
+}
+
+impl ParticleSystemSettings {
+	/// Checks the settings for values the particle system can't use,
+	/// like negative lifetimes or emission rates, and returns an error
+	/// describing the first problem found.
+	pub fn validate(&self) -> Result<(), WellspringError> {
+		let invalid = |reason: &str| Err(WellspringError::InvalidSettings(reason.to_string()));
+		let lifetime = &self.particle_lifetime;
+		if !lifetime.start.is_finite() || !lifetime.end.is_finite() {
+			return invalid("particle_lifetime must be finite");
+		}
+		if lifetime.start < 0.0 || lifetime.end < 0.0 {
+			return invalid("particle_lifetime must not be negative");
+		}
+		if !self.emission_rate.is_finite() || self.emission_rate < 0.0 {
+			return invalid("emission_rate must be a finite number that isn't negative");
+		}
+		if !self.emission_rate_variation.is_finite() || self.emission_rate_variation < 0.0 {
+			return invalid("emission_rate_variation must be a finite number that isn't negative");
+		}
+		if !self.speed.start.is_finite() || !self.speed.end.is_finite() {
+			return invalid("speed must be finite");
+		}
+		if self.sizes.keyframes.iter().any(|size| !size.is_finite()) {
+			return invalid("sizes must be finite");
+		}
+		let opacity = &self.opacity;
+		if !opacity.start.is_finite() || !opacity.end.is_finite() {
+			return invalid("opacity must be finite");
+		}
+		if opacity.start < 0.0 || opacity.end < 0.0 {
+			return invalid("opacity must not be negative");
+		}
+		if !self.spin.start.is_finite() || !self.spin.end.is_finite() {
+			return invalid("spin must be finite");
+		}
+		let mass = &self.mass;
+		if !mass.start.is_finite() || !mass.end.is_finite() {
+			return invalid("mass must be finite");
+		}
+		if mass.start <= 0.0 || mass.end <= 0.0 {
+			return invalid("mass must be greater than 0");
+		}
+		if let Some(cell_size) = self.query_cell_size {
+			if cell_size.is_nan() || cell_size <= 0.0 {
+				return invalid("query_cell_size must be greater than 0");
+			}
+		}
+		if let Some(limit) = &self.density_limit {
+			if limit.cell_size.is_nan() || limit.cell_size <= 0.0 {
+				return invalid("density_limit.cell_size must be greater than 0");
+			}
+		}
+		if let Some(interval) = self.update_interval {
+			if interval.is_nan() || interval <= 0.0 {
+				return invalid("update_interval must be greater than 0");
+			}
+		}
+		if let Some(radius) = self.activation_radius {
+			if radius.is_nan() || radius < 0.0 {
+				return invalid("activation_radius must not be negative");
+			}
+		}
+		if !self.wake_warm_up.is_finite() || self.wake_warm_up < 0.0 {
+			return invalid("wake_warm_up must be a finite number that isn't negative");
+		}
+		Ok(())
+	}
+
+	/// Returns the number of particles an emitter that runs forever
+	/// settles at: the emission rate times the average particle
+	/// lifetime. This can be used by tools and budget systems to warn
+	/// about expensive effects before they run.
+	///
+	/// The estimate assumes the particle system is at full strength,
+	/// and it doesn't include bursts, sub-emitters, emitter animations,
+	/// or particles that are removed early (e.g. by colliders).
+	pub fn steady_state_count(&self) -> f32 {
+		let lifetime =
+			ValueRange::from(&self.particle_lifetime).sample(self.lifetime_distribution.get_mean());
+		self.get_full_emission_rate() * lifetime
+	}
+
+	/// Returns an estimate of the most particles the particle system
+	/// will have at once, including the bursts and the initial burst.
+	/// Every particle is assumed to live as long as possible, so this
+	/// errs on the high side, and it's capped at `max_particles`.
+	/// See `steady_state_count`.
+	pub fn peak_count_estimate(&self) -> usize {
+		let lifetime = self.particle_lifetime.start.max(self.particle_lifetime.end);
+		let emission_time = match self.emitter_lifetime {
+			EmitterLifetime::Infinite => lifetime,
+			EmitterLifetime::Finite(duration) => lifetime.min(duration),
+		};
+		let continuous = (self.get_full_emission_rate() * emission_time).ceil() as usize;
+		// the most particles from bursts are alive right after one of
+		// the bursts happens
+		let bursts = self
+			.initial_burst
+			.map(|count| Burst { time: 0.0, count })
+			.into_iter()
+			.chain(self.bursts.iter().cloned())
+			.collect::<Vec<_>>();
+		let burst_peak = bursts
+			.iter()
+			.map(|burst| {
+				bursts
+					.iter()
+					.filter(|other| other.time <= burst.time && burst.time - other.time < lifetime)
+					.map(|other| other.count)
+					.sum::<usize>()
+			})
+			.max()
+			.unwrap_or(0);
+		let peak = continuous + burst_peak;
+		match self.max_particles {
+			Some(max_particles) => peak.min(max_particles),
+			None => peak,
+		}
+	}
+
+	/// Returns the emission rate when the particle system is at
+	/// full strength.
+	fn get_full_emission_rate(&self) -> f32 {
+		self.emission_rate
+			* StrengthResponse::get_multiplier(&self.strength_response.emission_rate, 1.0)
+	}
+
+	/// Returns the strongest acceleration the settings can give a
+	/// particle, ignoring forces, attractors, and modifiers.
+	fn get_max_acceleration(&self) -> f32 {
+		let acceleration = self
+			.acceleration
+			.start
+			.norm()
+			.max(self.acceleration.end.norm());
+		let buoyancy = self
+			.buoyancy
+			.keyframes
+			.iter()
+			.map(|b| b.abs())
+			.fold(0.0, f32::max);
+		let wind = match &self.wind {
+			Some(wind) => wind.base.norm() * (1.0 + wind.gust_strength.abs()),
+			None => 0.0,
+		};
+		let force = acceleration
+			+ get_max_magnitude(&self.radial_acceleration)
+			+ get_max_magnitude(&self.tangential_acceleration)
+			+ buoyancy
+			+ wind;
+		let brownian_motion = match &self.brownian_motion {
+			Some(brownian_motion) => brownian_motion.strength * brownian_motion.frequency,
+			None => 0.0,
+		};
+		force / self.mass.start.min(self.mass.end) + brownian_motion
+	}
+
+	/// Returns the farthest a particle moving at the given speed can
+	/// travel in `duration` seconds. See `get_max_acceleration`.
+	fn get_max_travel_distance(&self, speed: f32, duration: f32) -> f32 {
+		let distance = speed * duration + 0.5 * self.get_max_acceleration() * duration * duration;
+		match self.terminal_velocity {
+			Some(terminal_velocity) => distance.min(terminal_velocity.norm() * duration),
+			None => distance,
+		}
+	}
+}
+
+/// The particles in a particle system, along with the data structures
+/// used to move and find them.
+///
+/// A `ParticleSystem` is made of an `EmitterState`, a `ParticleBuffer`,
+/// and a `ParticleRenderer`. These parts can also be used on their own,
+/// e.g. to store them as separate components in an ECS and update and
+/// draw them in different systems.
+#[derive(Clone)]
+pub struct ParticleBuffer<T> {
+	particles: Vec<Particle<T>>,
+	emitted: usize,
+	spatial_hash: Option<SpatialHash>,
+	spatial_hash_valid: bool,
+	density_grid: Option<OccupancyGrid>,
+	density_grid_valid: bool,
+	#[cfg(feature = "repulsion")]
+	repulsion_spatial_hash: Option<SpatialHash>,
+	#[cfg(feature = "repulsion")]
+	repulsion_impulses: Vec<Vector2<f32>>,
+	#[cfg(feature = "repulsion")]
+	repulsion_neighbors: Vec<usize>,
+	lag: f32,
+	/// The number of particles fading out because of
+	/// `OverflowPolicy::FadeOldest`. Those are always the oldest
+	/// particles, so they're at the start of the list.
+	fading: usize,
+}
+
+impl<T> ParticleBuffer<T>
+where
+	T: graphics::Drawable,
+{
+	/// Creates an empty particle buffer.
+	pub fn new() -> Self {
+		Self {
+			particles: vec![],
+			emitted: 0,
+			spatial_hash: None,
+			spatial_hash_valid: false,
+			density_grid: None,
+			density_grid_valid: false,
+			#[cfg(feature = "repulsion")]
+			repulsion_spatial_hash: None,
+			#[cfg(feature = "repulsion")]
+			repulsion_impulses: vec![],
+			#[cfg(feature = "repulsion")]
+			repulsion_neighbors: vec![],
+			lag: 0.0,
+			fading: 0,
+		}
+	}
+
+	/// Returns the number of particles in the buffer.
+	pub fn count(&self) -> usize {
+		self.particles.len()
+	}
+
+	/// Reserves room for at least `additional` more particles than the
+	/// buffer currently has.
+	pub fn reserve(&mut self, additional: usize) {
+		self.particles.reserve(additional);
+	}
+
+	/// Removes all of the particles.
+	pub fn clear(&mut self) {
+		self.particles.clear();
+		self.fading = 0;
+		self.spatial_hash_valid = false;
+		self.density_grid_valid = false;
+	}
+
+	/// Removes all of the particles and forgets how many particles
+	/// were emitted and how much time was saved up, so a restarted
+	/// emitter numbers its particles the same way it did before.
+	fn reset(&mut self) {
+		self.clear();
+		self.emitted = 0;
+		self.lag = 0.0;
+	}
+
+	fn find_particle(&self, id: ParticleId) -> Option<usize> {
+		self.particles
+			.binary_search_by_key(&id.0, |particle| particle.index)
+			.ok()
+	}
+
+	/// Returns whether the particle with the given ID is still alive.
+	pub fn is_alive(&self, id: ParticleId) -> bool {
+		self.find_particle(id).is_some()
+	}
+
+	/// Returns the current state of the particle with the given ID,
+	/// or `None` if the particle is no longer alive.
+	pub fn get(&self, id: ParticleId) -> Option<ParticleView> {
+		self.find_particle(id)
+			.map(|index| self.particles[index].view())
+	}
+
+	/// Returns the IDs and current states of all the particles,
+	/// from oldest to newest.
+	pub fn particles(&self) -> impl Iterator<Item = (ParticleId, ParticleView)> + '_ {
+		self.particles
+			.iter()
+			.map(|particle| (ParticleId(particle.index), particle.view()))
+	}
+
+	/// Returns a light for each particle, for use with 2D lighting
+	/// systems. See `ParticleSystem::light_sources`.
+	pub fn light_sources<'a>(
+		&'a self,
+		mapping: &'a LightMapping,
+	) -> impl Iterator<Item = LightSample> + 'a {
+		self.particles
+			.iter()
+			.map(move |particle| mapping.get_light(&particle.view()))
+	}
+
+	/// Removes the particles that died this update. This keeps the
+	/// rest of the particles in order without shifting them once for
+	/// every dead particle.
+	fn remove_dead(&mut self) {
+		let fading = &mut self.fading;
+		self.particles.retain(|particle| {
+			if particle.dead && particle.fading {
+				*fading -= 1;
+			}
+			!particle.dead
+		});
+	}
+
+	/// Immediately removes the particle with the given ID. Returns
+	/// `false` if the particle was already dead.
+	pub fn kill(&mut self, id: ParticleId) -> bool {
+		match self.find_particle(id) {
+			Some(index) => {
+				if self.particles.remove(index).fading {
+					self.fading -= 1;
+				}
+				self.spatial_hash_valid = false;
+				self.density_grid_valid = false;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Sets how fast time passes for the particle with the given ID.
+	/// See `ParticleSystem::set_time_scale`. Returns `false` if the
+	/// particle is dead.
+	pub fn set_time_scale(&mut self, id: ParticleId, time_scale: f32) -> bool {
+		match self.find_particle(id) {
+			Some(index) => {
+				let particle = &mut self.particles[index];
+				particle.base_time_scale = time_scale;
+				particle.time_scale = time_scale;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Sets how fast time passes for every particle that matches a
+	/// condition. See `ParticleSystem::set_time_scale`.
+	pub fn set_time_scale_where<F>(&mut self, mut predicate: F, time_scale: f32)
+	where
+		F: FnMut(&ParticleView) -> bool,
+	{
+		for particle in &mut self.particles {
+			if predicate(&particle.view()) {
+				particle.base_time_scale = time_scale;
+				particle.time_scale = time_scale;
+			}
+		}
+	}
+
+	/// Moves and rotates all of the particles according to their
+	/// velocity and spin.
+	fn integrate(&mut self, delta_time: f32) {
+		for particle in &mut self.particles {
+			particle.integrate(delta_time * particle.time_scale);
+		}
+	}
+
+	#[cfg(feature = "repulsion")]
+	fn apply_repulsion(&mut self, repulsion: Option<&Repulsion>, delta_time: f32) {
+		let repulsion = match repulsion {
+			Some(repulsion) if repulsion.radius > 0.0 => repulsion,
+			_ => {
+				self.repulsion_spatial_hash = None;
+				return;
+			}
+		};
+		let radius = repulsion.radius;
+		if self
+			.repulsion_spatial_hash
+			.as_ref()
+			.map(SpatialHash::cell_size)
+			!= Some(radius)
+		{
+			self.repulsion_spatial_hash = Some(SpatialHash::new(radius));
+		}
+		let spatial_hash = match &mut self.repulsion_spatial_hash {
+			Some(spatial_hash) => spatial_hash,
+			None => return,
+		};
+		spatial_hash.rebuild(self.particles.iter().map(|particle| particle.position));
+		let impulses = &mut self.repulsion_impulses;
+		impulses.clear();
+		impulses.resize(self.particles.len(), Vector2::new(0.0, 0.0));
+		let neighbors = &mut self.repulsion_neighbors;
+		for (i, particle) in self.particles.iter().enumerate() {
+			let area = graphics::Rect::new(
+				particle.position.x - radius,
+				particle.position.y - radius,
+				radius * 2.0,
+				radius * 2.0,
+			);
+			spatial_hash.query_into(area, neighbors);
+			for &j in neighbors.iter() {
+				if i == j {
+					continue;
+				}
+				let difference = particle.position - self.particles[j].position;
+				let distance = difference.norm();
+				if distance == 0.0 || distance >= radius {
+					continue;
+				}
+				impulses[i] += difference / distance
+					* repulsion.strength
+					* (1.0 - distance / radius)
+					* delta_time;
+			}
+		}
+		for (particle, impulse) in self.particles.iter_mut().zip(impulses.iter()) {
+			particle.velocity += impulse;
+		}
+	}
+
+	fn rebuild_spatial_hash(&mut self, cell_size: Option<f32>) {
+		let cell_size = match cell_size {
+			Some(cell_size) => cell_size,
+			None => {
+				self.spatial_hash = None;
+				self.spatial_hash_valid = false;
+				return;
+			}
+		};
+		if self.spatial_hash.as_ref().map(SpatialHash::cell_size) != Some(cell_size) {
+			self.spatial_hash = Some(SpatialHash::new(cell_size));
+		}
+		if let Some(spatial_hash) = &mut self.spatial_hash {
+			spatial_hash.rebuild(self.particles.iter().map(|particle| particle.position));
+		}
+		self.spatial_hash_valid = true;
+	}
+
+	/// Returns whether a particle can be emitted at the given
+	/// position without going over the density limit.
+	fn has_room_at(&mut self, limit: &DensityLimit, position: Point2<f32>) -> bool {
+		if self.density_grid.as_ref().map(OccupancyGrid::cell_size) != Some(limit.cell_size) {
+			self.density_grid = Some(OccupancyGrid::new(limit.cell_size));
+			self.density_grid_valid = false;
+		}
+		match &mut self.density_grid {
+			Some(density_grid) => {
+				if !self.density_grid_valid {
+					density_grid.rebuild(self.particles.iter().map(|particle| particle.position));
+					self.density_grid_valid = true;
+				}
+				density_grid.count(position) < limit.max_particles
+			}
+			None => true,
+		}
+	}
+
+	fn get_query_candidates(&self, rect: graphics::Rect) -> Vec<usize> {
+		match &self.spatial_hash {
+			Some(spatial_hash) if self.spatial_hash_valid => spatial_hash.query(rect),
+			_ => (0..self.particles.len()).collect(),
+		}
+	}
+
+	/// Returns the IDs and current states of all the particles
+	/// within a rectangle.
+	pub fn query_rect(&self, rect: graphics::Rect) -> Vec<(ParticleId, ParticleView)> {
+		self.get_query_candidates(rect)
+			.into_iter()
+			.map(|i| &self.particles[i])
+			.filter(|particle| rect.contains(particle.position))
+			.map(|particle| (ParticleId(particle.index), particle.view()))
+			.collect()
+	}
+
+	/// Returns the IDs and current states of all the particles
+	/// within a circle.
+	pub fn query_circle(
+		&self,
+		center: Point2<f32>,
+		radius: f32,
+	) -> Vec<(ParticleId, ParticleView)> {
+		let rect = graphics::Rect::new(
+			center.x - radius,
+			center.y - radius,
+			radius * 2.0,
+			radius * 2.0,
+		);
+		self.get_query_candidates(rect)
+			.into_iter()
+			.map(|i| &self.particles[i])
+			.filter(|particle| (particle.position - center).norm() <= radius)
+			.map(|particle| (ParticleId(particle.index), particle.view()))
+			.collect()
+	}
+
+	/// Adds to the velocity of every particle in a region, e.g. to blow
+	/// smoke away from an explosion.
+	pub fn apply_impulse(&mut self, region: Region, impulse: Vector2<f32>) {
+		for particle in &mut self.particles {
+			if region.contains(particle.position) {
+				particle.velocity += impulse;
+			}
+		}
+	}
+
+	/// Gives every particle in a region more time to live. See
+	/// `ParticleSystem::extend_lifetime`.
+	pub fn extend_lifetime(&mut self, region: Region, extra_time: f32) {
+		self.extend_lifetime_where(|particle| region.contains(particle.position), extra_time);
+	}
+
+	/// Gives every particle that matches a condition more time to
+	/// live. See `ParticleSystem::extend_lifetime_where`.
+	pub fn extend_lifetime_where<F>(&mut self, mut predicate: F, extra_time: f32)
+	where
+		F: FnMut(&ParticleView) -> bool,
+	{
+		for particle in &mut self.particles {
+			if predicate(&particle.view()) {
+				particle.extend_lifetime(extra_time);
+			}
+		}
+	}
+}
+
+impl<T> Default for ParticleBuffer<T>
+where
+	T: graphics::Drawable,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> std::fmt::Debug for ParticleBuffer<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ParticleBuffer")
+			.field("count", &self.particles.len())
+			.field("emitted", &self.emitted)
+			.field("lag", &self.lag)
+			.finish()
+	}
+}
+
+/// A line that the emitter moves along.
+#[derive(Clone)]
+struct EmitterPath {
+	points: Vec<Point2<f32>>,
+	duration: f32,
+	looping: bool,
+	time: f32,
+}
+
+impl EmitterPath {
+	/// Returns the point on the path at the current time. The emitter
+	/// moves at a constant speed along the whole path.
+	fn get_position(&self) -> Option<Point2<f32>> {
+		let first = *self.points.first()?;
+		let length: f32 = self
+			.points
+			.windows(2)
+			.map(|segment| (segment[1] - segment[0]).norm())
+			.sum();
+		if length == 0.0 || self.duration <= 0.0 {
+			return Some(first);
+		}
+		let progress = if self.looping {
+			(self.time / self.duration).rem_euclid(1.0)
+		} else {
+			(self.time / self.duration).min(1.0)
+		};
+		let mut distance = progress * length;
+		for segment in self.points.windows(2) {
+			let segment_length = (segment[1] - segment[0]).norm();
+			if distance <= segment_length && segment_length > 0.0 {
+				return Some(segment[0] + (segment[1] - segment[0]) * (distance / segment_length));
+			}
+			distance -= segment_length;
+		}
+		self.points.last().copied()
+	}
+}
+
+fn get_particle_position_offset(
+	emitter_shape: &EmitterShape,
+	uniform_area_sampling: bool,
+	rng: &mut StdRng,
+) -> Vector2<f32> {
+	match emitter_shape {
+		EmitterShape::Point => Vector2::new(0.0, 0.0),
+		EmitterShape::Composite(shapes) => {
+			let total_weight: f32 = shapes.iter().map(|(_, weight)| weight.max(0.0)).sum();
+			let mut choice = total_weight * rng.gen::<f32>();
+			for (shape, weight) in shapes {
+				let weight = weight.max(0.0);
+				if choice < weight {
+					return get_particle_position_offset(shape, uniform_area_sampling, rng);
+				}
+				choice -= weight;
+			}
+			Vector2::new(0.0, 0.0)
+		}
+		EmitterShape::Rectangle(size, angle) => {
+			Rotation2::new(*angle)
+				* Vector2::new(
+					lerp(-size.x / 2.0, size.x / 2.0, rng.gen::<f32>()),
+					lerp(-size.y / 2.0, size.y / 2.0, rng.gen::<f32>()),
+				)
+		}
+		EmitterShape::Ellipse(size, angle) => {
+			let particle_angle = 2.0 * std::f32::consts::PI * rng.gen::<f32>();
+			let mut distance = rng.gen::<f32>();
+			if uniform_area_sampling {
+				distance = distance.sqrt();
+			}
+			Rotation2::new(*angle)
+				* Vector2::new(
+					distance * particle_angle.cos() * size.x,
+					distance * particle_angle.sin() * size.y,
+				)
+		}
+		EmitterShape::RectangleBorder(size, angle) => {
+			let top_left = Vector2::new(-size.x / 2.0, -size.y / 2.0);
+			let top_right = Vector2::new(size.x / 2.0, -size.y / 2.0);
+			let bottom_right = Vector2::new(size.x / 2.0, size.y / 2.0);
+			let bottom_left = Vector2::new(-size.x / 2.0, size.y / 2.0);
+			let side_boundaries = [
+				size.x,
+				size.x + size.y,
+				size.x * 2.0 + size.y,
+				size.x * 2.0 + size.y * 2.0,
+			];
+			let amount = side_boundaries[3] * rng.gen::<f32>();
+			let offset = if amount > side_boundaries[2] {
+				lerp(
+					bottom_left,
+					top_left,
+					(amount - side_boundaries[2]) / (side_boundaries[3] - side_boundaries[2]),
+				)
+			} else if amount > side_boundaries[1] {
+				lerp(
+					bottom_right,
+					bottom_left,
+					(amount - side_boundaries[1]) / (side_boundaries[2] - side_boundaries[1]),
+				)
+			} else if amount > side_boundaries[0] {
+				lerp(
+					top_right,
+					bottom_right,
+					(amount - side_boundaries[0]) / (side_boundaries[1] - side_boundaries[0]),
+				)
+			} else {
+				lerp(top_left, top_right, amount / side_boundaries[0])
+			};
+			Rotation2::new(*angle) * offset
+		}
+		EmitterShape::EllipseBorder(size, angle) => {
+			let particle_angle = 2.0 * std::f32::consts::PI * rng.gen::<f32>();
+			Rotation2::new(*angle)
+				* Vector2::new(particle_angle.cos() * size.x, particle_angle.sin() * size.y)
+		}
+	}
+}
+
+type TriggerCallback = Rc<dyn Fn(ParticleView)>;
+
+#[derive(Clone)]
+enum TriggerKind {
+	SpawnEveryNth(usize),
+	FirstCollision,
+}
+
+/// A callback for events like particles spawning, meant for
+/// playing sound effects.
+#[derive(Clone)]
+struct Trigger {
+	kind: TriggerKind,
+	min_interval: f32,
+	callback: TriggerCallback,
+	count: usize,
+	last_fired: Option<f32>,
+}
+
+impl Trigger {
+	/// Calls the callback unless it was already called less than
+	/// `min_interval` seconds ago.
+	fn fire(&mut self, clock: f32, particle: ParticleView) {
+		if let Some(last_fired) = self.last_fired {
+			if clock - last_fired < self.min_interval {
+				return;
+			}
+		}
+		self.last_fired = Some(clock);
+		(self.callback)(particle);
+	}
+}
+
+/// Identifies one of the emitters in a particle system.
+/// See `ParticleSystem::add_emitter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EmitterId(usize);
+
+impl EmitterId {
+	/// The particle system's main emitter, which uses the position
+	/// and shape from the settings.
+	pub const MAIN: EmitterId = EmitterId(0);
+}
+
+/// Settings for one emitter in a particle system that override the
+/// particle system's settings, e.g. so one torch in a row of torches
+/// can go out. See `ParticleSystem::emitter_overrides_mut`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EmitterOverrides {
+	/// Whether the emitter emits particles on its own. Disabled
+	/// emitters don't use the emission rate (or, for the main emitter,
+	/// the bursts), but `emit` still works.
+	pub enabled: bool,
+	/// The number of particles the emitter produces per second,
+	/// instead of the `emission_rate` setting.
+	pub emission_rate: Option<f32>,
+	/// The initial direction of particles from this emitter (in
+	/// radians), instead of the `angle` setting.
+	pub angle: Option<f32>,
+}
+
+impl Default for EmitterOverrides {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			emission_rate: None,
+			angle: None,
+		}
+	}
+}
+
+/// An extra emitter added with `ParticleSystem::add_emitter`.
+#[derive(Debug, Clone)]
+struct Emitter {
+	position: Point2<f32>,
+	shape: EmitterShape,
+	overrides: EmitterOverrides,
+	emit_timer: f32,
+}
+
+/// The state of a particle emitter: its timers, random number
+/// generator, and path. The emitter creates particles in a
+/// `ParticleBuffer` and updates them according to a particle
+/// system's settings. See `ParticleBuffer`.
+#[derive(Clone)]
+pub struct EmitterState {
+	rng: StdRng,
+	seed: Option<u64>,
+	keyframes: Option<SharedKeyframes>,
+	path: Option<EmitterPath>,
+	camera: Option<graphics::Rect>,
+	listener: Option<Point2<f32>>,
+	asleep: bool,
+	local_sub_emissions: Vec<Point2<f32>>,
+	sub_emissions: Vec<SubEmission>,
+	emitting_sub_particles: bool,
+	emitters: Vec<Option<Emitter>>,
+	main_overrides: EmitterOverrides,
+	emitting_from: EmitterId,
+	triggers: Vec<Trigger>,
+	forces: Vec<Option<Force>>,
+	budget_cursor: usize,
+	previous_position: Option<Point2<f32>>,
+	motion_angle: f32,
+	strength: f32,
+	running: bool,
+	finishing_bursts: bool,
+	initial_burst_pending: bool,
+	emit_timer: f32,
+	time: f32,
+	clock: f32,
+}
+
+impl EmitterState {
+	/// Creates a new emitter that starts out running.
+	pub fn new() -> Self {
+		Self {
+			rng: StdRng::from_entropy(),
+			seed: None,
+			keyframes: None,
+			path: None,
+			camera: None,
+			listener: None,
+			asleep: false,
+			local_sub_emissions: vec![],
+			sub_emissions: vec![],
+			emitting_sub_particles: false,
+			emitters: vec![],
+			main_overrides: EmitterOverrides::default(),
+			emitting_from: EmitterId::MAIN,
+			triggers: vec![],
+			forces: vec![],
+			budget_cursor: 0,
+			previous_position: None,
+			motion_angle: 0.0,
+			strength: 1.0,
+			running: true,
+			finishing_bursts: false,
+			initial_burst_pending: true,
+			emit_timer: 1.0,
+			time: 0.0,
+			clock: 0.0,
+		}
+	}
+
+	/// Seeds the emitter's random number generator. See
+	/// `ParticleSystem::set_seed`.
+	pub fn set_seed(&mut self, seed: u64) {
+		self.rng = StdRng::seed_from_u64(seed);
+		self.seed = Some(seed);
+	}
+
+	/// Returns whether the emitter is currently creating new particles.
+	pub fn running(&self) -> bool {
+		self.running
+	}
+
+	/// Returns how many seconds the emitter has been updated for.
+	/// See `ParticleSystem::clock`.
+	pub fn clock(&self) -> f32 {
+		self.clock
+	}
+
+	/// Returns the strength of the emitter, from 0 to 1.
+	/// See `ParticleSystem::strength`.
+	pub fn strength(&self) -> f32 {
+		self.strength
+	}
+
+	/// Sets the strength of the emitter, from 0 to 1.
+	/// See `ParticleSystem::set_strength`.
+	pub fn set_strength(&mut self, strength: f32) {
+		self.strength = strength.clamp(0.0, 1.0);
+	}
+
+	/// Starts the emitter.
+	pub fn start(&mut self) {
+		if self.running {
+			self.finishing_bursts = false;
+			return;
+		}
+		self.running = true;
+		self.initial_burst_pending = true;
+		self.emit_timer = 1.0;
+		self.time = 0.0;
+	}
+
+	/// Removes all particles from the buffer and starts the emitter
+	/// from the beginning. See `ParticleSystem::restart`.
+	pub fn restart<T>(&mut self, buffer: &mut ParticleBuffer<T>)
+	where
+		T: graphics::Drawable,
+	{
+		buffer.reset();
+		self.running = true;
+		self.finishing_bursts = false;
+		self.initial_burst_pending = true;
+		self.emit_timer = 1.0;
+		self.time = 0.0;
+		self.clock = 0.0;
+		self.asleep = false;
+		self.budget_cursor = 0;
+		self.previous_position = None;
+		self.motion_angle = 0.0;
+		self.local_sub_emissions.clear();
+		self.sub_emissions.clear();
+		if let Some(path) = &mut self.path {
+			path.time = 0.0;
+		}
+		for emitter in self.emitters.iter_mut().flatten() {
+			emitter.emit_timer = 1.0;
+		}
+		for trigger in &mut self.triggers {
+			trigger.count = 0;
+			trigger.last_fired = None;
+		}
+		// every particle's random numbers come from a seed picked by
+		// this generator, so reseeding it replays those too
+		if let Some(seed) = self.seed {
+			self.rng = StdRng::seed_from_u64(seed);
+		}
+	}
+
+	/// Replaces the emitter's timers, random number generator, and
+	/// path progress with a copy saved by `History`. The triggers,
+	/// forces, extra emitters, emitter overrides, camera, and listener
+	/// are kept, since they're set up by the game rather than changed
+	/// by updates, but the extra emitters' timers and the triggers'
+	/// counts are restored too. Emitters and triggers that were added
+	/// after the copy was saved start over as if they were new.
+	fn restore_run_state(&mut self, saved: &EmitterState) {
+		for (index, emitter) in self.emitters.iter_mut().enumerate() {
+			if let Some(emitter) = emitter {
+				emitter.emit_timer = match saved.emitters.get(index) {
+					Some(Some(saved)) => saved.emit_timer,
+					_ => 1.0,
+				};
+			}
+		}
+		for (index, trigger) in self.triggers.iter_mut().enumerate() {
+			let (count, last_fired) = saved
+				.triggers
+				.get(index)
+				.map_or((0, None), |saved| (saved.count, saved.last_fired));
+			trigger.count = count;
+			trigger.last_fired = last_fired;
+		}
+		let mut restored = saved.clone();
+		restored.triggers = std::mem::take(&mut self.triggers);
+		restored.forces = std::mem::take(&mut self.forces);
+		restored.emitters = std::mem::take(&mut self.emitters);
+		restored.main_overrides = std::mem::take(&mut self.main_overrides);
+		restored.camera = self.camera;
+		restored.listener = self.listener;
+		*self = restored;
+	}
+
+	/// Stops the emitter. Existing particles are left alone.
+	pub fn stop(&mut self) {
+		self.running = false;
+		self.finishing_bursts = false;
+	}
+
+	/// Stops the emitter using the given `StopMode`.
+	pub fn stop_with<T>(&mut self, mode: StopMode, buffer: &mut ParticleBuffer<T>)
+	where
+		T: graphics::Drawable,
+	{
+		match mode {
+			StopMode::EmissionOnly => self.stop(),
+			StopMode::ClearParticles => {
+				self.stop();
+				buffer.clear();
+			}
+			StopMode::AfterScheduledBursts => {
+				if self.running {
+					self.finishing_bursts = true;
+				}
+			}
+		}
+	}
+
+	/// Returns the time until the next particle should be emitted,
+	/// as a fraction of the average time between particles.
+	fn get_emit_interval(&mut self, settings: &ParticleSystemSettings) -> f32 {
+		let variation = settings.emission_rate_variation.clamp(0.0, 1.0);
+		if variation == 0.0 {
+			return 1.0;
+		}
+		let offset = variation * (self.rng.gen::<f32>() * 2.0 - 1.0);
+		(1.0 + offset).max(0.001)
+	}
+
+	fn has_pending_bursts(&self, settings: &ParticleSystemSettings) -> bool {
+		settings.bursts.iter().any(|burst| burst.time >= self.time)
+	}
+
+	/// Makes the emitter move along a line through the given points.
+	/// See `ParticleSystem::set_path`.
+	pub fn set_path(&mut self, points: Vec<Point2<f32>>, duration: f32, looping: bool) {
+		self.path = Some(EmitterPath {
+			points,
+			duration,
+			looping,
+			time: 0.0,
+		});
+	}
+
+	/// Stops the emitter from moving along the path set by `set_path`.
+	pub fn clear_path(&mut self) {
+		self.path = None;
+	}
+
+	/// Calls a function every `n`th time a particle is emitted, but
+	/// no more than once every `min_interval` seconds. See
+	/// `ParticleSystem::on_spawn_every_nth`.
+	pub fn on_spawn_every_nth<F>(&mut self, n: usize, min_interval: f32, callback: F)
+	where
+		F: Fn(ParticleView) + 'static,
+	{
+		self.triggers.push(Trigger {
+			kind: TriggerKind::SpawnEveryNth(n.max(1)),
+			min_interval,
+			callback: Rc::new(callback),
+			count: 0,
+			last_fired: None,
+		});
+	}
+
+	/// Calls a function when a particle hits a collider for the first
+	/// time, but no more than once every `min_interval` seconds. See
+	/// `ParticleSystem::on_first_collision`.
+	pub fn on_first_collision<F>(&mut self, min_interval: f32, callback: F)
+	where
+		F: Fn(ParticleView) + 'static,
+	{
+		self.triggers.push(Trigger {
+			kind: TriggerKind::FirstCollision,
+			min_interval,
+			callback: Rc::new(callback),
+			count: 0,
+			last_fired: None,
+		});
+	}
+
+	/// Removes all of the functions added by `on_spawn_every_nth`
+	/// and `on_first_collision`.
+	pub fn clear_triggers(&mut self) {
+		self.triggers.clear();
+	}
+
+	/// Adds a force that can be changed or removed later using the
+	/// returned handle. See `ParticleSystem::add_force`.
+	pub fn add_force(&mut self, force: Force) -> ForceHandle {
+		self.forces.push(Some(force));
+		ForceHandle(self.forces.len() - 1)
+	}
+
+	/// Returns a mutable reference to the force with the given handle.
+	pub fn force_mut(&mut self, handle: ForceHandle) -> Option<&mut Force> {
+		self.forces.get_mut(handle.0).and_then(Option::as_mut)
+	}
+
+	/// Moves the force with the given handle. See
+	/// `ParticleSystem::set_force_position`.
+	pub fn set_force_position(&mut self, handle: ForceHandle, position: Point2<f32>) {
+		if let Some(force) = self.force_mut(handle) {
+			force.set_position(position);
+		}
+	}
+
+	/// Removes the force with the given handle and returns it.
+	pub fn remove_force(&mut self, handle: ForceHandle) -> Option<Force> {
+		self.forces.get_mut(handle.0).and_then(Option::take)
+	}
+
+	/// Sets the area of the world the camera can see, which screen
+	/// anchored emitters follow. See `ParticleSystem::update_with_camera`.
+	pub fn set_camera(&mut self, camera: Option<graphics::Rect>) {
+		self.camera = camera;
+	}
+
+	/// Sets the position of the listener, e.g. the camera or player,
+	/// that wakes up the emitter. See `ParticleSystem::set_listener`.
+	pub fn set_listener(&mut self, listener: Point2<f32>) {
+		self.listener = Some(listener);
+	}
+
+	/// Removes the listener, which keeps the emitter awake. See
+	/// `ParticleSystem::clear_listener`.
+	pub fn clear_listener(&mut self) {
+		self.listener = None;
+	}
+
+	/// Returns whether the emitter is asleep because the listener is
+	/// too far away. See `ParticleSystem::is_asleep`.
+	pub fn is_asleep(&self) -> bool {
+		self.asleep
+	}
+
+	/// Estimates the area the particles could cover in the next
+	/// `horizon` seconds. See `ParticleSystem::predicted_bounds`.
+	pub fn predicted_bounds<T>(
+		&self,
+		settings: &ParticleSystemSettings,
+		buffer: &ParticleBuffer<T>,
+		horizon: f32,
+	) -> Option<graphics::Rect> {
+		let horizon = horizon.max(0.0);
+		let mut bounds: Option<(Point2<f32>, Point2<f32>)> = None;
+		let mut include = |position: Point2<f32>, radius: f32| {
+			let (min, max) = (
+				position.coords.add_scalar(-radius),
+				position.coords.add_scalar(radius),
+			);
+			bounds = Some(match bounds {
+				Some((a, b)) => (
+					Point2::new(a.x.min(min.x), a.y.min(min.y)),
+					Point2::new(b.x.max(max.x), b.y.max(max.y)),
+				),
+				None => (Point2::from(min), Point2::from(max)),
+			});
+		};
+		for particle in &buffer.particles {
+			let remaining = (1.0 - particle.time).max(0.0) * particle.lifetime;
+			let duration = horizon.min(remaining);
+			let distance = settings.get_max_travel_distance(particle.velocity.norm(), duration);
+			include(particle.position, distance);
+		}
+		if self.running || self.finishing_bursts {
+			let lifetime = settings
+				.particle_lifetime
+				.start
+				.max(settings.particle_lifetime.end);
+			let speed = get_max_magnitude(&settings.speed)
+				* StrengthResponse::get_multiplier(
+					&settings.strength_response.speed,
+					self.strength,
+				);
+			let distance = settings.get_max_travel_distance(speed, horizon.min(lifetime));
+			include(
+				self.get_emitter_position(settings),
+				settings.shape.get_extent() + distance,
+			);
+			if let Some(path) = &self.path {
+				for &point in &path.points {
+					include(
+						self.anchor(settings, point),
+						settings.shape.get_extent() + distance,
+					);
+				}
+			}
+			for emitter in self.emitters.iter().flatten() {
+				include(
+					self.anchor(settings, emitter.position),
+					emitter.shape.get_extent() + distance,
+				);
+			}
+		}
+		bounds.map(|(min, max)| graphics::Rect::new(min.x, min.y, max.x - min.x, max.y - min.y))
+	}
+
+	/// Returns whether the listener is close enough to any of the
+	/// emitters for the particle system to be updated.
+	fn listener_in_range(&self, settings: &ParticleSystemSettings) -> bool {
+		let (radius, listener) = match (settings.activation_radius, self.listener) {
+			(Some(radius), Some(listener)) => (radius, listener),
+			_ => return true,
+		};
+		self.emitters()
+			.filter_map(|id| self.get_source_position(settings, id))
+			.any(|position| (position - listener).norm() <= radius)
+	}
+
+	/// Puts the emitter to sleep if the listener is out of range and
+	/// wakes it up (warming it up if needed) if the listener came back.
+	/// Returns whether the emitter is awake.
+	fn update_activation<T>(
+		&mut self,
+		settings: &mut ParticleSystemSettings,
+		modifiers: &mut [Box<dyn Modifier>],
+		buffer: &mut ParticleBuffer<T>,
+	) -> bool
+	where
+		T: graphics::Drawable,
+	{
+		if !self.listener_in_range(settings) {
+			self.asleep = true;
+			return false;
+		}
+		if self.asleep {
+			self.asleep = false;
+			let mut remaining = settings.wake_warm_up;
+			while remaining > 0.0 {
+				let step = remaining.min(WARM_UP_STEP);
+				self.advance(settings, modifiers, buffer, step);
+				remaining -= step;
+			}
+		}
+		true
+	}
+
+	fn get_emitter_position(&self, settings: &ParticleSystemSettings) -> Point2<f32> {
+		self.anchor(settings, settings.position)
+	}
+
+	/// Moves a position to follow the camera if the emitter is
+	/// screen anchored.
+	fn anchor(&self, settings: &ParticleSystemSettings, position: Point2<f32>) -> Point2<f32> {
+		match (settings.screen_anchored, self.camera) {
+			(true, Some(camera)) => position + Vector2::new(camera.x, camera.y),
+			_ => position,
+		}
+	}
+
+	/// Returns the position of one of the emitters, or `None` if the
+	/// emitter was removed.
+	fn get_source_position(
+		&self,
+		settings: &ParticleSystemSettings,
+		source: EmitterId,
+	) -> Option<Point2<f32>> {
+		match source.0 {
+			0 => Some(self.get_emitter_position(settings)),
+			_ => self
+				.get_emitter(source)
+				.map(|emitter| self.anchor(settings, emitter.position)),
+		}
+	}
+
+	/// Adds an emitter that emits particles into the same buffer.
+	/// See `ParticleSystem::add_emitter`.
+	pub fn add_emitter(&mut self, position: Point2<f32>, shape: EmitterShape) -> EmitterId {
+		self.emitters.push(Some(Emitter {
+			position,
+			shape,
+			overrides: EmitterOverrides::default(),
+			emit_timer: 1.0,
+		}));
+		EmitterId(self.emitters.len())
+	}
+
+	/// Removes an emitter added with `add_emitter`. Returns `false` if
+	/// the emitter was already removed or is the main emitter.
+	pub fn remove_emitter(&mut self, id: EmitterId) -> bool {
+		match id
+			.0
+			.checked_sub(1)
+			.and_then(|index| self.emitters.get_mut(index))
+		{
+			Some(emitter) => emitter.take().is_some(),
+			None => false,
+		}
+	}
+
+	/// Returns an emitter added with `add_emitter`, or `None` if the
+	/// emitter was removed or the ID is from another particle system.
+	fn get_emitter(&self, id: EmitterId) -> Option<&Emitter> {
+		id.0.checked_sub(1)
+			.and_then(|index| self.emitters.get(index))
+			.and_then(Option::as_ref)
+	}
+
+	/// Returns a mutable reference to an emitter added with `add_emitter`.
+	fn get_emitter_mut(&mut self, id: EmitterId) -> Option<&mut Emitter> {
+		id.0.checked_sub(1)
+			.and_then(move |index| self.emitters.get_mut(index))
+			.and_then(Option::as_mut)
+	}
+
+	/// Moves an emitter added with `add_emitter`. Returns `false` if the
+	/// emitter was removed or is the main emitter.
+	pub fn set_emitter_position(&mut self, id: EmitterId, position: Point2<f32>) -> bool {
+		match self.get_emitter_mut(id) {
+			Some(emitter) => {
+				emitter.position = position;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Changes the shape of an emitter added with `add_emitter`. Returns
+	/// `false` if the emitter was removed or is the main emitter.
+	pub fn set_emitter_shape(&mut self, id: EmitterId, shape: EmitterShape) -> bool {
+		match self.get_emitter_mut(id) {
+			Some(emitter) => {
+				emitter.shape = shape;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Returns the overrides for one of the emitters, or `None` if the
+	/// emitter was removed. See `ParticleSystem::emitter_overrides`.
+	pub fn emitter_overrides(&self, id: EmitterId) -> Option<&EmitterOverrides> {
+		match id.0 {
+			0 => Some(&self.main_overrides),
+			_ => self.get_emitter(id).map(|emitter| &emitter.overrides),
+		}
+	}
+
+	/// Returns a mutable reference to the overrides for one of the
+	/// emitters, or `None` if the emitter was removed.
+	/// See `ParticleSystem::emitter_overrides_mut`.
+	pub fn emitter_overrides_mut(&mut self, id: EmitterId) -> Option<&mut EmitterOverrides> {
+		match id.0 {
+			0 => Some(&mut self.main_overrides),
+			_ => self
+				.get_emitter_mut(id)
+				.map(|emitter| &mut emitter.overrides),
+		}
+	}
+
+	/// Returns the IDs of the emitters added with `add_emitter` that
+	/// haven't been removed.
+	pub fn emitters(&self) -> impl Iterator<Item = EmitterId> + '_ {
+		self.emitters
+			.iter()
+			.enumerate()
+			.filter(|(_, emitter)| emitter.is_some())
+			.map(|(index, _)| EmitterId(index + 1))
+	}
+
+	/// Emits a single particle. If `position` is `None`, the particle
+	/// is spawned in the emitter's shape, and if `angle` is `None`,
+	/// the particle's direction is picked using the angle and spread
+	/// settings.
+	fn emit_particle<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		drawable: Option<T>,
+		position: Option<Point2<f32>>,
+		angle: Option<f32>,
+	) -> Option<ParticleId>
+	where
+		T: graphics::Drawable,
+	{
+		if let Some(max_particles) = settings.max_particles {
+			let particles = &mut buffer.particles;
+			match settings.overflow_policy {
+				OverflowPolicy::Block => {
+					if particles.len() >= max_particles {
+						return None;
+					}
+				}
+				OverflowPolicy::KillOldest => {
+					if max_particles == 0 {
+						return None;
+					}
+					if particles.len() >= max_particles {
+						let excess = particles.len() + 1 - max_particles;
+						// the oldest particles die the same way they would
+						// at the end of their lifetimes
+						for particle in particles.drain(..excess) {
+							if particle.fading {
+								buffer.fading -= 1;
+							}
+							self.trigger_death_sub_emitters(settings, particle.position);
+						}
+						buffer.spatial_hash_valid = false;
+						buffer.density_grid_valid = false;
+					}
+				}
+				OverflowPolicy::FadeOldest(duration) => {
+					if particles.len() - buffer.fading >= max_particles {
+						// the particles that are already fading out are
+						// the oldest ones, so the next one is right after them
+						if let Some(particle) = particles.get_mut(buffer.fading) {
+							particle.fade_out(duration);
+							buffer.fading += 1;
+						}
+					}
+				}
+			}
+		}
+		let seed: u64 = self.rng.gen();
+		let emitter_position = self
+			.get_source_position(settings, self.emitting_from)
+			.unwrap_or_else(|| self.get_emitter_position(settings));
+		let position = match position {
+			Some(position) => position,
+			None => {
+				let origin = match (&settings.spawn_mode, buffer.particles.last()) {
+					(SpawnMode::Chain, Some(particle)) => particle.position,
+					_ => emitter_position,
+				};
+				// the emitters are borrowed separately from the rng
+				let emitters = &self.emitters;
+				let shape = self
+					.emitting_from
+					.0
+					.checked_sub(1)
+					.and_then(|index| emitters.get(index))
+					.and_then(Option::as_ref)
+					.map_or(&settings.shape, |emitter| &emitter.shape);
+				origin
+					+ get_particle_position_offset(
+						shape,
+						settings.uniform_area_sampling,
+						&mut self.rng,
+					)
+			}
+		};
+		if let Some(limit) = &settings.density_limit {
+			if !buffer.has_room_at(limit, position) {
+				return None;
+			}
+		}
+		let angle = angle.unwrap_or_else(|| {
+			self.get_emission_angle(
+				settings,
+				ParticleRng::for_parameter(seed, Parameter::Angle).gen(),
+			)
+		});
+		let speed =
+			get_rand_in_range(
+				&settings.speed,
+				&mut ParticleRng::for_parameter(seed, Parameter::Speed),
+			) * StrengthResponse::get_multiplier(&settings.strength_response.speed, self.strength);
+		let velocity = Vector2::new(speed * angle.cos(), speed * angle.sin());
+		let keyframes = match &self.keyframes {
+			Some(keyframes) if keyframes.matches(settings) => keyframes.clone(),
+			_ => {
+				let keyframes = SharedKeyframes::new(settings);
+				self.keyframes = Some(keyframes.clone());
+				keyframes
+			}
+		};
+		let sample = |range: &Range<f32>, parameter: Parameter| {
+			get_rand_in_range(range, &mut ParticleRng::for_parameter(seed, parameter))
+		};
+		buffer.particles.push(Particle {
+			sizes: keyframes.sizes,
+			size_scale: StrengthResponse::get_multiplier(
+				&settings.strength_response.size,
+				self.strength,
+			),
+			colors: keyframes.colors,
+			opacity: sample(&settings.opacity, Parameter::Opacity),
+			opacity_over_lifetime: keyframes.opacity,
+			buoyancy: keyframes.buoyancy,
+			lifetime: ValueRange::from(&settings.particle_lifetime).sample(
+				settings
+					.lifetime_distribution
+					.apply(ParticleRng::for_parameter(seed, Parameter::Lifetime).gen()),
+			),
+			time: 0.0,
+			position,
+			spawn_position: position,
+			kill_radius: settings
+				.kill_radius
+				.as_ref()
+				.map(|range| sample(range, Parameter::KillRadius)),
+			velocity,
+			initial_speed: speed,
+			damping: sample(&settings.damping, Parameter::Damping),
+			terminal_velocity: settings.terminal_velocity,
+			mass: sample(&settings.mass, Parameter::Mass),
+			acceleration: get_rand_vector_in_range(
+				&settings.acceleration,
+				&mut ParticleRng::for_parameter(seed, Parameter::Acceleration),
+			),
+			radial_acceleration: sample(
+				&settings.radial_acceleration,
+				Parameter::RadialAcceleration,
+			),
+			tangential_acceleration: sample(
+				&settings.tangential_acceleration,
+				Parameter::TangentialAcceleration,
+			),
+			angle: 0.0,
+			spin: sample(&settings.spin, Parameter::Spin),
+			spin_damping: sample(&settings.spin_damping, Parameter::SpinDamping),
+			phase_offset: sample(&settings.phase_offset, Parameter::PhaseOffset),
+			updated_at: self.clock,
+			brownian_motion: settings.brownian_motion,
+			brownian_motion_timer: 0.0,
+			use_relative_angle: settings.use_relative_angle,
+			facing: settings.facing.clone(),
+			emitter_position,
+			offset: settings.offset,
+			scale: 1.0,
+			tint: graphics::WHITE,
+			time_scale: 1.0,
+			base_time_scale: 1.0,
+			seed,
+			rng: ParticleRng::for_parameter(seed, Parameter::Motion),
+			index: buffer.emitted,
+			bounces: 0,
+			max_bounces: settings.max_bounces,
+			sub_emitted: self.emitting_sub_particles,
+			fading: false,
+			source: self.emitting_from,
+			shown: false,
+			dead: false,
+			drawable,
+		});
+		let id = ParticleId(buffer.emitted);
+		buffer.emitted += 1;
+		buffer.spatial_hash_valid = false;
+		if let Some(density_grid) = &mut buffer.density_grid {
+			density_grid.add(position);
+		}
+		if let Some(particle) = buffer.particles.last() {
+			for trigger in &mut self.triggers {
+				if let TriggerKind::SpawnEveryNth(n) = trigger.kind {
+					trigger.count += 1;
+					if trigger.count % n == 0 {
+						trigger.fire(self.clock, particle.view());
+					}
+				}
+			}
+		}
+		self.trigger_sub_emitters(
+			&settings.sub_emitters,
+			position,
+			self.emitting_sub_particles,
+			|sub_emitter| match sub_emitter.trigger {
+				SubEmitterTrigger::Birth => sub_emitter.count,
+				_ => 0,
+			},
+		);
+		Some(id)
+	}
+
+	/// Immediately emits the specified number of particles into the
+	/// buffer. See `ParticleSystem::emit`.
+	pub fn emit<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		count: usize,
+	) -> Vec<ParticleId>
+	where
+		T: graphics::Drawable,
+	{
+		(0..count)
+			.filter_map(|_| self.emit_particle(settings, buffer, None, None, None))
+			.collect()
+	}
+
+	/// Immediately emits the specified number of particles into the
+	/// buffer, all moving in the same direction.
+	/// See `ParticleSystem::emit_uniform`.
+	pub fn emit_uniform<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		count: usize,
+	) -> Vec<ParticleId>
+	where
+		T: graphics::Drawable,
+	{
+		let value = self.rng.gen();
+		let angle = self.get_emission_angle(settings, value);
+		(0..count)
+			.filter_map(|_| self.emit_particle(settings, buffer, None, None, Some(angle)))
+			.collect()
+	}
+
+	/// Immediately emits a single particle into the buffer.
+	/// See `ParticleSystem::emit_one`.
+	pub fn emit_one<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+	) -> Option<ParticleId>
+	where
+		T: graphics::Drawable,
+	{
+		self.emit_particle(settings, buffer, None, None, None)
+	}
+
+	/// Immediately emits one particle at each of the given points into
+	/// the buffer. See `ParticleSystem::emit_from_points`.
+	pub fn emit_from_points<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		points: impl IntoIterator<Item = Point2<f32>>,
+	) -> Vec<ParticleId>
+	where
+		T: graphics::Drawable,
+	{
+		points
+			.into_iter()
+			.filter_map(|point| self.emit_particle(settings, buffer, None, Some(point), None))
+			.collect()
+	}
+
+	/// Immediately emits the given number of particles at a point
+	/// into the buffer. See `ParticleSystem::emit_at_point`.
+	pub fn emit_at_point<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		point: Point2<f32>,
+		count: usize,
+	) where
+		T: graphics::Drawable,
+	{
+		for _ in 0..count {
+			self.emit_particle(settings, buffer, None, Some(point), None);
+		}
+	}
+
+	/// Immediately emits a burst of particles in the given pattern
+	/// into the buffer. See `ParticleSystem::burst`.
+	pub fn burst<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		pattern: BurstPattern,
+	) -> Vec<ParticleId>
+	where
+		T: graphics::Drawable,
+	{
+		// the fraction of the way through the pattern each particle is at
+		let get_fraction = |i: usize, count: usize, closed: bool| match (closed, count) {
+			(true, _) => i as f32 / count as f32,
+			(false, 0) | (false, 1) => 0.5,
+			(false, _) => i as f32 / (count - 1) as f32,
+		};
+		let emitter_position = self.get_emitter_position(settings);
+		match pattern {
+			BurstPattern::Ring { count, radius } => (0..count)
+				.filter_map(|i| {
+					let angle = get_fraction(i, count, true) * 2.0 * std::f32::consts::PI;
+					let position =
+						emitter_position + Vector2::new(angle.cos(), angle.sin()) * radius;
+					self.emit_particle(settings, buffer, None, Some(position), Some(angle))
+				})
+				.collect(),
+			BurstPattern::Cone {
+				count,
+				angle,
+				spread,
+			} => (0..count)
+				.filter_map(|i| {
+					let angle = angle - spread / 2.0 + spread * get_fraction(i, count, false);
+					self.emit_particle(settings, buffer, None, None, Some(angle))
+				})
+				.collect(),
+			BurstPattern::Line { count, from, to } => (0..count)
+				.filter_map(|i| {
+					let position = from + (to - from) * get_fraction(i, count, false);
+					self.emit_particle(settings, buffer, None, Some(position), None)
+				})
+				.collect(),
+		}
+	}
+
+	/// Moves the emitter, emits new particles, and advances the
+	/// emitter's timers.
+	fn advance_emitter<T>(
+		&mut self,
+		settings: &mut ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		delta_time: f32,
+	) where
+		T: graphics::Drawable,
+	{
+		if let Some(path) = &mut self.path {
+			if let Some(position) = path.get_position() {
+				settings.position = position;
+			}
+			path.time += delta_time;
+		}
+		if settings.angle_follows_motion {
+			self.track_motion(settings);
+		}
+		// emit new particles
 		if self.running {
+			if let Some(animation) = settings.animation.take() {
+				animation.apply(settings, self.time);
+				settings.animation = Some(animation);
+			}
+			let main_enabled = self.main_overrides.enabled;
+			if self.initial_burst_pending {
+				self.initial_burst_pending = false;
+				if let (Some(count), true) = (settings.initial_burst, main_enabled) {
+					self.emit(settings, buffer, count);
+				}
+			}
+			if !self.finishing_bursts {
+				let rate_multiplier = StrengthResponse::get_multiplier(
+					&settings.strength_response.emission_rate,
+					self.strength,
+				);
+				let get_amount = |overrides: &EmitterOverrides| {
+					overrides.emission_rate.unwrap_or(settings.emission_rate)
+						* rate_multiplier * delta_time
+				};
+				if main_enabled {
+					self.emit_timer -= get_amount(&self.main_overrides);
+					while self.emit_timer <= 0.0 {
+						self.emit_timer += self.get_emit_interval(settings);
+						self.emit_particle(settings, buffer, None, None, None);
+					}
+				}
+				for index in 0..self.emitters.len() {
+					let mut emit_timer = match &self.emitters[index] {
+						Some(emitter) if emitter.overrides.enabled => {
+							emitter.emit_timer - get_amount(&emitter.overrides)
+						}
+						_ => continue,
+					};
+					self.emitting_from = EmitterId(index + 1);
+					while emit_timer <= 0.0 {
+						emit_timer += self.get_emit_interval(settings);
+						self.emit_particle(settings, buffer, None, None, None);
+					}
+					if let Some(emitter) = &mut self.emitters[index] {
+						emitter.emit_timer = emit_timer;
+					}
+				}
+				self.emitting_from = EmitterId::MAIN;
+			}
+			let previous_time = self.time;
+			self.time += delta_time;
+			let burst_count: usize = settings
+				.bursts
+				.iter()
+				.filter(|burst| burst.time >= previous_time && burst.time < self.time)
+				.map(|burst| burst.count)
+				.sum();
+			if main_enabled {
+				for _ in 0..burst_count {
+					self.emit_particle(settings, buffer, None, None, None);
+				}
+			}
+			if self.finishing_bursts && !self.has_pending_bursts(settings) {
+				self.stop();
+			}
+			if let EmitterLifetime::Finite(time) = settings.emitter_lifetime {
+				if self.time >= time {
+					self.stop();
+				}
+			}
+		}
+		self.clock += delta_time;
+	}
+
+	/// Updates the direction the emitter is moving in from how far it's
+	/// moved since the last update.
+	fn track_motion(&mut self, settings: &ParticleSystemSettings) {
+		let position = self.get_emitter_position(settings);
+		if let Some(previous_position) = self.previous_position {
+			let motion = position - previous_position;
+			if motion.norm_squared() > 0.0 {
+				self.motion_angle = motion.y.atan2(motion.x);
+			}
+		}
+		self.previous_position = Some(position);
+	}
+
+	/// Picks a direction for a new particle using the angle, spread,
+	/// and spread distribution settings. `value` is a random number
+	/// from 0 to 1.
+	fn get_emission_angle(&self, settings: &ParticleSystemSettings, value: f32) -> f32 {
+		let offset = settings.spread_distribution.apply(value);
+		let angle = self
+			.emitter_overrides(self.emitting_from)
+			.and_then(|overrides| overrides.angle)
+			.unwrap_or(settings.angle);
+		let base_angle = if settings.angle_follows_motion {
+			self.motion_angle + angle
+		} else {
+			angle
+		};
+		base_angle + offset * settings.spread / 2.0
+	}
+
+	/// Returns the wind force and the bounds that particles wrap
+	/// around in, which are the same for every particle.
+	fn get_environment(
+		&self,
+		settings: &ParticleSystemSettings,
+	) -> (Vector2<f32>, Option<graphics::Rect>) {
+		let wind_force = match &settings.wind {
+			Some(wind) => wind.get_force(self.clock),
+			None => Vector2::new(0.0, 0.0),
+		};
+		let wrap_bounds = if settings.screen_anchored {
+			self.camera
+		} else {
+			None
+		};
+		(wind_force, wrap_bounds)
+	}
+
+	/// Applies the forces to a particle and updates its velocity
+	/// (without moving it).
+	fn apply_forces<T>(
+		&self,
+		settings: &ParticleSystemSettings,
+		particle: &mut Particle<T>,
+		delta_time: f32,
+		emitter_position: Point2<f32>,
+		wind_force: Vector2<f32>,
+	) where
+		T: graphics::Drawable,
+	{
+		// particles from removed emitters stay where their emitter was
+		let emitter_position = match particle.source {
+			EmitterId::MAIN => emitter_position,
+			source => self
+				.get_source_position(settings, source)
+				.unwrap_or(particle.emitter_position),
+		};
+		let mut external_force = wind_force;
+		for force in settings.forces.iter().chain(self.forces.iter().flatten()) {
+			external_force += force.get_force(
+				particle.position,
+				particle.velocity,
+				self.clock,
+				particle.time,
+				self.time,
+			);
+		}
+		particle.update(delta_time, emitter_position, external_force);
+		particle.updated_at = self.clock;
+	}
+
+	/// Runs the modifiers, collisions, and kill conditions on a
+	/// particle that has just moved. Returns whether the particle died.
+	fn finish_particle<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		modifiers: &mut [Box<dyn Modifier>],
+		particle: &mut Particle<T>,
+		delta_time: f32,
+		wrap_bounds: Option<graphics::Rect>,
+	) -> bool
+	where
+		T: graphics::Drawable,
+	{
+		if particle.time >= 1.0 && !particle.shown {
+			// particles always outlive their first update, so particles
+			// that live for less than a frame are drawn once (as they
+			// looked when they were emitted) instead of flickering.
+			// this only applies to running out of time; particles that
+			// are killed below still die right away
+			particle.time = 0.0;
+		}
+		particle.shown = true;
+		if !modifiers.is_empty() {
+			particle.apply_modifiers(modifiers, delta_time);
+		}
+		#[cfg(feature = "debug_checks")]
+		particle.check(settings);
+		if let Some(bounds) = wrap_bounds {
+			particle.wrap(bounds);
+		}
+		let bounces = particle.bounces;
+		for collider in &settings.colliders {
+			particle.collide(collider);
+		}
+		if bounces == 0 && particle.bounces > 0 {
+			for trigger in &mut self.triggers {
+				if let TriggerKind::FirstCollision = trigger.kind {
+					trigger.fire(self.clock, particle.view());
+				}
+			}
+		}
+		if let Some((bounds, behavior)) = &settings.world_bounds {
+			match behavior {
+				BoundsBehavior::Kill => {
+					if !bounds.contains(particle.position) {
+						particle.time = 1.0;
+					}
+				}
+				BoundsBehavior::Wrap => particle.wrap(*bounds),
+				BoundsBehavior::Bounce => particle.bounce(*bounds),
+			}
+		}
+		if settings.kill_when_transparent && particle.get_color().a <= TRANSPARENT_ALPHA_THRESHOLD {
+			particle.time = 1.0;
+		}
+		if let Some(kill_radius) = particle.kill_radius {
+			if (particle.position - particle.spawn_position).norm() >= kill_radius {
+				particle.time = 1.0;
+			}
+		}
+		let age = particle.time.min(1.0) * particle.lifetime;
+		let previous_age = age - delta_time;
+		self.trigger_sub_emitters(
+			&settings.sub_emitters,
+			particle.position,
+			particle.sub_emitted,
+			|sub_emitter| match sub_emitter.trigger {
+				SubEmitterTrigger::Trail(rate) => {
+					let ticks = (age * rate).floor() - (previous_age * rate).floor();
+					ticks.max(0.0) as usize * sub_emitter.count
+				}
+				_ => 0,
+			},
+		);
+		particle.time >= 1.0
+	}
+
+	/// Marks a particle as dead, so it's removed at the end of the
+	/// update, and triggers its death sub-emitters.
+	fn mark_dead<T>(&mut self, settings: &ParticleSystemSettings, particle: &mut Particle<T>)
+	where
+		T: graphics::Drawable,
+	{
+		particle.dead = true;
+		self.trigger_death_sub_emitters(settings, particle.position);
+	}
+
+	/// Queues particles from the sub-emitters that emit when a
+	/// particle dies at the given position.
+	fn trigger_death_sub_emitters(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		position: Point2<f32>,
+	) {
+		self.trigger_sub_emitters(&settings.sub_emitters, position, false, |sub_emitter| {
+			match sub_emitter.trigger {
+				SubEmitterTrigger::Death => sub_emitter.count,
+				_ => 0,
+			}
+		});
+	}
+
+	/// Emits the particles queued by sub-emitters and updates the
+	/// data structures used for finding particles.
+	fn finish_update<T>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		delta_time: f32,
+	) where
+		T: graphics::Drawable,
+	{
+		// the particles have moved since the density grid was built
+		buffer.density_grid_valid = false;
+		if !self.local_sub_emissions.is_empty() {
+			let mut points = std::mem::take(&mut self.local_sub_emissions);
+			self.emitting_sub_particles = true;
+			for point in points.drain(..) {
+				self.emit_particle(settings, buffer, None, Some(point), None);
+			}
+			self.emitting_sub_particles = false;
+			// keep the buffer around so it doesn't need to be allocated again
+			self.local_sub_emissions = points;
+		}
+		#[cfg(feature = "repulsion")]
+		buffer.apply_repulsion(settings.repulsion.as_ref(), delta_time);
+		#[cfg(not(feature = "repulsion"))]
+		let _ = delta_time;
+		buffer.rebuild_spatial_hash(settings.query_cell_size);
+	}
+
+	/// Updates the emitter and the particles in the buffer by the given
+	/// number of seconds. The settings are changed if the emitter is
+	/// animated or moving along a path.
+	///
+	/// If the settings have an `update_interval`, the time is saved up
+	/// until a full interval has passed, and then everything is
+	/// simulated at once. Nothing is updated while the emitter is
+	/// asleep (see `ParticleSystemSettings::activation_radius`).
+	pub fn advance<T>(
+		&mut self,
+		settings: &mut ParticleSystemSettings,
+		modifiers: &mut [Box<dyn Modifier>],
+		buffer: &mut ParticleBuffer<T>,
+		delta_time: f32,
+	) where
+		T: graphics::Drawable,
+	{
+		if !self.update_activation(settings, modifiers, buffer) {
+			return;
+		}
+		let delta_time = match Self::take_update_time(settings, buffer, delta_time) {
+			Some(delta_time) => delta_time,
+			None => return,
+		};
+		self.advance_emitter(settings, buffer, delta_time);
+		// update existing particles
+		let emitter_position = self.get_emitter_position(settings);
+		let (wind_force, wrap_bounds) = self.get_environment(settings);
+		for particle in &mut buffer.particles {
+			let particle_delta_time = delta_time * particle.time_scale;
+			self.apply_forces(
+				settings,
+				particle,
+				particle_delta_time,
+				emitter_position,
+				wind_force,
+			);
+		}
+		buffer.integrate(delta_time);
+		for particle in buffer.particles.iter_mut().rev() {
+			let particle_delta_time = delta_time * particle.time_scale;
+			let dead = self.finish_particle(
+				settings,
+				modifiers,
+				particle,
+				particle_delta_time,
+				wrap_bounds,
+			);
+			if dead {
+				self.mark_dead(settings, particle);
+			}
+		}
+		buffer.remove_dead();
+		self.finish_update(settings, buffer, delta_time);
+	}
+
+	/// Saves up time until a full `update_interval` has passed. Returns
+	/// the amount of time to simulate, or `None` if the particle system
+	/// shouldn't be updated yet.
+	fn take_update_time<T>(
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<T>,
+		delta_time: f32,
+	) -> Option<f32>
+	where
+		T: graphics::Drawable,
+	{
+		let delta_time = match settings.update_interval {
+			Some(interval) => {
+				buffer.lag += delta_time;
+				if buffer.lag < interval {
+					return None;
+				}
+				buffer.lag
+			}
+			None => delta_time,
+		};
+		buffer.lag = 0.0;
+		Some(delta_time)
+	}
+
+	/// Updates the emitter, then updates as many particles as possible
+	/// in the given amount of time. See `ParticleSystem::advance_budgeted`.
+	pub fn advance_budgeted<T>(
+		&mut self,
+		settings: &mut ParticleSystemSettings,
+		modifiers: &mut [Box<dyn Modifier>],
+		buffer: &mut ParticleBuffer<T>,
+		delta_time: f32,
+		budget: Duration,
+	) where
+		T: graphics::Drawable,
+	{
+		let start = Instant::now();
+		if !self.update_activation(settings, modifiers, buffer) {
 			return;
 		}
-		self.running = true;
-		self.emit_timer = 1.0;
-		self.time = 0.0;
+		let delta_time = match Self::take_update_time(settings, buffer, delta_time) {
+			Some(delta_time) => delta_time,
+			None => return,
+		};
+		self.advance_emitter(settings, buffer, delta_time);
+		let emitter_position = self.get_emitter_position(settings);
+		let (wind_force, wrap_bounds) = self.get_environment(settings);
+		let mut index = self.budget_cursor;
+		let mut remaining = buffer.particles.len();
+		let mut updated = 0;
+		while remaining > 0 {
+			remaining -= 1;
+			if index >= buffer.particles.len() {
+				index = 0;
+			}
+			let particle = &mut buffer.particles[index];
+			// particles that were skipped in earlier updates catch up
+			// on all of the time they missed
+			let particle_delta_time = (self.clock - particle.updated_at) * particle.time_scale;
+			self.apply_forces(
+				settings,
+				particle,
+				particle_delta_time,
+				emitter_position,
+				wind_force,
+			);
+			particle.integrate(particle_delta_time);
+			let dead = self.finish_particle(
+				settings,
+				modifiers,
+				particle,
+				particle_delta_time,
+				wrap_bounds,
+			);
+			if dead {
+				self.mark_dead(settings, particle);
+			}
+			index += 1;
+			// checking the time is relatively slow, so only do it
+			// every few particles
+			updated += 1;
+			if updated % BUDGET_CHECK_INTERVAL == 0 && start.elapsed() >= budget {
+				break;
+			}
+		}
+		// the particles before the cursor that died are about to be
+		// removed, so the cursor moves back to stay on the same particle
+		let dead_before_cursor = buffer.particles[..index.min(buffer.particles.len())]
+			.iter()
+			.filter(|particle| particle.dead)
+			.count();
+		self.budget_cursor = index - dead_before_cursor;
+		buffer.remove_dead();
+		self.finish_update(settings, buffer, delta_time);
+	}
+
+	/// Queues particles from each sub-emitter at the given position.
+	/// `get_count` returns the number of particles each sub-emitter
+	/// should emit. If `skip_local` is `true`, sub-emitters targeting
+	/// this particle system are ignored.
+	fn trigger_sub_emitters<F>(
+		&mut self,
+		sub_emitters: &[SubEmitter],
+		position: Point2<f32>,
+		skip_local: bool,
+		get_count: F,
+	) where
+		F: Fn(&SubEmitter) -> usize,
+	{
+		for sub_emitter in sub_emitters {
+			let count = get_count(sub_emitter);
+			if count == 0 {
+				continue;
+			}
+			match sub_emitter.target {
+				Some(target) => self.sub_emissions.push(SubEmission {
+					target,
+					position,
+					count,
+				}),
+				None => {
+					if skip_local {
+						continue;
+					}
+					for _ in 0..count {
+						self.local_sub_emissions.push(position);
+					}
+				}
+			}
+		}
+	}
+
+	/// Returns and clears the requests from sub-emitters to emit
+	/// particles into other particle systems.
+	pub fn take_sub_emissions(&mut self) -> Vec<SubEmission> {
+		std::mem::take(&mut self.sub_emissions)
+	}
+
+	/// Reserves room for sub-emitters to queue particles for at least
+	/// `additional` more particles in a single update, so the queues
+	/// don't need to grow when lots of particles die at once.
+	pub fn reserve(&mut self, additional: usize) {
+		self.local_sub_emissions.reserve(additional);
+		self.sub_emissions.reserve(additional);
+	}
+
+	/// Moves the requests from sub-emitters to emit particles into
+	/// other particle systems onto the end of a list. Unlike
+	/// `take_sub_emissions`, the emitter keeps its own list allocated
+	/// for the next update.
+	pub fn drain_sub_emissions(&mut self, sub_emissions: &mut Vec<SubEmission>) {
+		sub_emissions.append(&mut self.sub_emissions);
+	}
+}
+
+impl Default for EmitterState {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl std::fmt::Debug for EmitterState {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("EmitterState")
+			.field("running", &self.running)
+			.field("asleep", &self.asleep)
+			.field("finishing_bursts", &self.finishing_bursts)
+			.field("time", &self.time)
+			.field("clock", &self.clock)
+			.field("seed", &self.seed)
+			.field("has_path", &self.path.is_some())
+			.field("pending_sub_emissions", &self.sub_emissions.len())
+			.finish()
+	}
+}
+
+impl EmitterState {
+	/// Immediately emits the specified number of particles into the
+	/// buffer, each displaying its own text. See `ParticleSystem::emit_text`.
+	pub fn emit_text<F>(
+		&mut self,
+		settings: &ParticleSystemSettings,
+		buffer: &mut ParticleBuffer<graphics::Text>,
+		count: usize,
+		mut text: F,
+	) -> Vec<ParticleId>
+	where
+		F: FnMut(usize) -> graphics::Text,
+	{
+		(0..count)
+			.filter_map(|i| {
+				let mut text = text(i);
+				if settings.color_mode == ColorMode::Replace {
+					for fragment in text.fragments_mut() {
+						fragment.color = None;
+					}
+				}
+				self.emit_particle(settings, buffer, Some(text), None, None)
+			})
+			.collect()
+	}
+}
+
+type DrawOverride = Rc<dyn Fn(&mut Context, ParticleView) -> GameResult>;
+type UseShader = Rc<dyn Fn(&mut Context) -> graphics::ShaderLock>;
+
+/// Draws the particles in a `ParticleBuffer`. See `ParticleBuffer`.
+///
+/// Clones of a renderer share its draw override, shader, and canvas.
+#[derive(Clone)]
+pub struct ParticleRenderer<D>
+where
+	D: DrawableProvider,
+{
+	drawable: D,
+	draw_override: Option<DrawOverride>,
+	shader: Option<UseShader>,
+	canvas: Option<Rc<RefCell<graphics::Canvas>>>,
+	blend_mode: Option<graphics::BlendMode>,
+	batch_vertices: RefCell<Vec<graphics::Vertex>>,
+	batch_indices: RefCell<Vec<u32>>,
+	batch_mesh: RefCell<Option<graphics::Mesh>>,
+	/// The dimensions of each drawable used this frame, by address.
+	dimensions: RefCell<HashMap<usize, Option<graphics::Rect>>>,
+}
+
+impl<D> ParticleRenderer<D>
+where
+	D: DrawableProvider,
+{
+	/// Creates a new renderer using the specified drawable object
+	/// (or `DrawableProvider`) to display each particle.
+	pub fn new(drawable: D) -> Self {
+		Self {
+			drawable,
+			draw_override: None,
+			shader: None,
+			canvas: None,
+			blend_mode: None,
+			batch_vertices: RefCell::new(vec![]),
+			batch_indices: RefCell::new(vec![]),
+			batch_mesh: RefCell::new(None),
+			dimensions: RefCell::new(HashMap::new()),
+		}
+	}
+
+	/// Sets the blend mode used to draw the particles. See
+	/// `ParticleSystem::set_blend_mode`.
+	pub fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		self.drawable.set_blend_mode(mode);
+		self.blend_mode = mode;
+	}
+
+	/// Returns the blend mode used to draw the particles, if one was set.
+	pub fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		self.blend_mode
+	}
+
+	/// Sets a function to call to draw each particle instead of
+	/// drawing the renderer's drawable. See `ParticleSystem::set_draw_override`.
+	pub fn set_draw_override<F>(&mut self, draw_override: F)
+	where
+		F: Fn(&mut Context, ParticleView) -> GameResult + 'static,
+	{
+		self.draw_override = Some(Rc::new(draw_override));
+	}
+
+	/// Removes the function set by `set_draw_override`.
+	pub fn clear_draw_override(&mut self) {
+		self.draw_override = None;
+	}
+
+	/// Sets a shader to use while drawing the particles. See
+	/// `ParticleSystem::set_shader`.
+	pub fn set_shader<C>(&mut self, shader: Option<graphics::Shader<C>>)
+	where
+		C: 'static + gfx::pso::buffer::Structure<gfx::shade::ConstFormat>,
+	{
+		self.shader = match shader {
+			Some(shader) => Some(Rc::new(move |ctx| graphics::use_shader(ctx, &shader))),
+			None => None,
+		};
+	}
+
+	/// Makes the renderer draw the particles onto a canvas the size of
+	/// the window. See `ParticleSystem::enable_canvas`.
+	pub fn enable_canvas(&mut self, ctx: &mut Context) -> Result<(), WellspringError> {
+		self.canvas = Some(Rc::new(RefCell::new(graphics::Canvas::with_window_size(
+			ctx,
+		)?)));
+		Ok(())
+	}
+
+	/// Makes the renderer draw the particles directly to the current
+	/// render target again.
+	pub fn disable_canvas(&mut self) {
+		self.canvas = None;
+	}
+
+	fn get_particle_drawable<'a>(
+		&'a self,
+		particle: &Particle<D::Drawable>,
+	) -> (&'a D::Drawable, Option<graphics::Rect>) {
+		self.drawable.drawable(
+			particle.get_animation_time() * particle.lifetime,
+			particle.seed,
+			particle.index,
+		)
+	}
+
+	/// Returns the draw parameters for each particle in the buffer
+	/// without drawing anything. See `ParticleSystem::instance_params`.
+	pub fn instance_params<'a>(
+		&'a self,
+		buffer: &'a ParticleBuffer<D::Drawable>,
+	) -> impl Iterator<Item = graphics::DrawParam> + 'a {
+		buffer.particles.iter().map(move |particle| {
+			let src = match particle.drawable {
+				Some(_) => None,
+				None => self.get_particle_drawable(particle).1,
+			};
+			particle.draw_param(src, buffer.lag)
+		})
+	}
+
+	/// Returns the dimensions of a drawable. Each drawable's dimensions
+	/// are only looked up once per frame, since many particles usually
+	/// share the same drawable.
+	fn get_dimensions(&self, ctx: &mut Context, drawable: &D::Drawable) -> Option<graphics::Rect> {
+		let key = drawable as *const D::Drawable as usize;
+		*self
+			.dimensions
+			.borrow_mut()
+			.entry(key)
+			.or_insert_with(|| graphics::Drawable::dimensions(drawable, ctx))
+	}
+
+	fn draw_lines(
+		&self,
+		ctx: &mut Context,
+		buffer: &ParticleBuffer<D::Drawable>,
+		width: f32,
+		offset: Vector2<f32>,
+	) -> GameResult {
+		let mut mesh_builder = graphics::MeshBuilder::new();
+		let mut has_segments = false;
+		for pair in buffer.particles.windows(2) {
+			let (a, b) = (&pair[0], &pair[1]);
+			let (a_position, b_position) = (
+				a.get_draw_position(buffer.lag),
+				b.get_draw_position(buffer.lag),
+			);
+			if a_position == b_position {
+				continue;
+			}
+			let size = lerp(a.get_size(), b.get_size(), 0.5);
+			mesh_builder.line(&[a_position, b_position], width * size, a.get_color())?;
+			has_segments = true;
+		}
+		if !has_segments {
+			return Ok(());
+		}
+		let mut mesh = mesh_builder.build(ctx)?;
+		graphics::Drawable::set_blend_mode(&mut mesh, self.blend_mode);
+		graphics::draw(
+			ctx,
+			&mesh,
+			graphics::DrawParam::new().dest(Point2::new(-offset.x, -offset.y)),
+		)
+	}
+
+	fn draw_particles(
+		&self,
+		ctx: &mut Context,
+		settings: &ParticleSystemSettings,
+		buffer: &ParticleBuffer<D::Drawable>,
+		offset: Vector2<f32>,
+		bounds: Option<graphics::Rect>,
+	) -> GameResult {
+		let _shader_lock = self.shader.as_ref().map(|use_shader| use_shader(ctx));
+		// drawables can change size between frames (e.g. text)
+		self.dimensions.borrow_mut().clear();
+		match settings.render_mode {
+			RenderMode::Sprites => {
+				for particle in &buffer.particles {
+					let (drawable, src) = self.get_particle_drawable(particle);
+					if let Some(bounds) = bounds {
+						let dimensions = self
+							.get_dimensions(ctx, particle.drawable.as_ref().unwrap_or(drawable));
+						if !particle.is_visible(dimensions, bounds, buffer.lag) {
+							continue;
+						}
+					}
+					if let Some(draw_override) = &self.draw_override {
+						let mut view = particle.view();
+						view.position = particle.get_draw_position(buffer.lag) - offset;
+						draw_override(ctx, view)?;
+						continue;
+					}
+					particle.draw(ctx, drawable, src, offset, buffer.lag)?;
+				}
+				Ok(())
+			}
+			RenderMode::Lines(width) => self.draw_lines(ctx, buffer, width, offset),
+		}
+	}
+
+	fn draw_to_target(
+		&self,
+		ctx: &mut Context,
+		settings: &ParticleSystemSettings,
+		buffer: &ParticleBuffer<D::Drawable>,
+		param: graphics::DrawParam,
+		offset: Vector2<f32>,
+		bounds: Option<graphics::Rect>,
+	) -> GameResult {
+		match &self.canvas {
+			Some(canvas) => {
+				let mut canvas = canvas.borrow_mut();
+				graphics::set_canvas(ctx, Some(&canvas));
+				graphics::clear(ctx, Color::new(0.0, 0.0, 0.0, 0.0));
+				let result = self.draw_particles(ctx, settings, buffer, offset, bounds);
+				graphics::set_canvas(ctx, None);
+				result?;
+				// clones of the renderer share the canvas but can have
+				// different blend modes
+				graphics::Drawable::set_blend_mode(&mut *canvas, self.blend_mode);
+				graphics::draw(ctx, &*canvas, param)
+			}
+			None => self.draw_particles(ctx, settings, buffer, offset, bounds),
+		}
+	}
+
+	/// Draws the particles in the buffer. `param` is only used
+	/// when drawing to a canvas.
+	pub fn draw(
+		&self,
+		ctx: &mut Context,
+		settings: &ParticleSystemSettings,
+		buffer: &ParticleBuffer<D::Drawable>,
+		param: graphics::DrawParam,
+	) -> GameResult {
+		self.draw_to_target(ctx, settings, buffer, param, Vector2::new(0.0, 0.0), None)
+	}
+
+	/// Draws the particles in the buffer as seen by a camera. See
+	/// `ParticleSystem::draw_with_camera`.
+	pub fn draw_with_camera(
+		&self,
+		ctx: &mut Context,
+		settings: &ParticleSystemSettings,
+		buffer: &ParticleBuffer<D::Drawable>,
+		camera: graphics::Rect,
+		parallax: f32,
+	) -> GameResult {
+		let offset = Vector2::new(camera.x, camera.y) * parallax;
+		let bounds = graphics::Rect::new(offset.x, offset.y, camera.w, camera.h);
+		self.draw_to_target(
+			ctx,
+			settings,
+			buffer,
+			graphics::DrawParam::new(),
+			offset,
+			Some(bounds),
+		)
+	}
+}
+
+impl<D> std::fmt::Debug for ParticleRenderer<D>
+where
+	D: DrawableProvider,
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ParticleRenderer")
+			.field("has_draw_override", &self.draw_override.is_some())
+			.field("has_shader", &self.shader.is_some())
+			.field("has_canvas", &self.canvas.is_some())
+			.field("blend_mode", &self.blend_mode)
+			.finish()
+	}
+}
+
+impl ParticleRenderer<graphics::Image> {
+	/// Draws all of the particles in the buffer with a single draw call.
+	/// See `ParticleSystem::draw_batched`.
+	pub fn draw_batched(
+		&self,
+		ctx: &mut Context,
+		buffer: &ParticleBuffer<graphics::Image>,
+		param: graphics::DrawParam,
+	) -> GameResult {
+		let mut sprite_batch = graphics::spritebatch::SpriteBatch::new(self.drawable.clone());
+		for instance_param in self.instance_params(buffer) {
+			sprite_batch.add(instance_param);
+		}
+		graphics::Drawable::set_blend_mode(&mut sprite_batch, self.blend_mode);
+		let _shader_lock = self.shader.as_ref().map(|use_shader| use_shader(ctx));
+		graphics::draw(ctx, &sprite_batch, param)
+	}
+}
+
+impl ParticleRenderer<ParticleMesh> {
+	/// Draws all of the particles in the buffer with a single draw call.
+	/// See `ParticleSystem::draw_batched`.
+	pub fn draw_batched(
+		&self,
+		ctx: &mut Context,
+		buffer: &ParticleBuffer<ParticleMesh>,
+		param: graphics::DrawParam,
+	) -> GameResult {
+		let mut vertices = self.batch_vertices.borrow_mut();
+		let mut indices = self.batch_indices.borrow_mut();
+		vertices.clear();
+		indices.clear();
+		for particle in &buffer.particles {
+			let mesh = particle.drawable.as_ref().unwrap_or(&self.drawable);
+			let position = particle.get_draw_position(buffer.lag).coords;
+			let size = particle.get_size();
+			let rotation = Rotation2::new(particle.get_angle());
+			let offset = particle.offset.coords;
+			// the same transform ggez uses to draw a mesh with the
+			// particle's draw parameters
+			mesh.append_transformed(&mut vertices, &mut indices, particle.get_color(), |point| {
+				Point2::from(position + offset + rotation * ((point.coords - offset) * size))
+			});
+		}
+		if indices.is_empty() {
+			return Ok(());
+		}
+		// the combined mesh is kept between frames so its memory
+		// can be reused
+		let mut batch_mesh = self.batch_mesh.borrow_mut();
+		let mesh = match &mut *batch_mesh {
+			Some(mesh) => {
+				mesh.set_vertices(ctx, &vertices, &indices);
+				mesh
+			}
+			None => {
+				batch_mesh.get_or_insert(graphics::Mesh::from_raw(ctx, &vertices, &indices, None)?)
+			}
+		};
+		graphics::Drawable::set_blend_mode(mesh, self.blend_mode);
+		let _shader_lock = self.shader.as_ref().map(|use_shader| use_shader(ctx));
+		graphics::draw(ctx, mesh, param)
+	}
+}
+
+/// Manages and displays particles.
+///
+/// More specifically, a `ParticleSystem` is a "world" containing:
+/// - particles
+/// - an "emitter" that produces particles at a regular interval
+///   or on demand
+/// - rules governing how the particles move
+///
+/// The emitter, particles, and drawable are stored in an `EmitterState`,
+/// a `ParticleBuffer`, and a `ParticleRenderer`, which can also be used
+/// separately.
+///
+/// Cloning a particle system copies its settings, modifiers, and live
+/// particles, e.g. for mirrored effects. The clone's random number
+/// generator starts in the same state as the original's, so use
+/// `set_seed` to make the clone behave differently.
+pub struct ParticleSystem<D>
+where
+	D: DrawableProvider,
+{
+	/// The settings defining how the particle system behaves.
+	pub settings: ParticleSystemSettings,
+	/// Modifiers that change every particle each update, in order.
+	pub modifiers: Vec<Box<dyn Modifier>>,
+	emitter: EmitterState,
+	buffer: ParticleBuffer<D::Drawable>,
+	renderer: ParticleRenderer<D>,
+}
+
+impl<D> ParticleSystem<D>
+where
+	D: DrawableProvider,
+{
+	/// Creates a new particle system using the specified drawable object
+	/// (or `DrawableProvider`) to display each particle.
+	pub fn new(drawable: D, settings: ParticleSystemSettings) -> Self {
+		Self {
+			settings,
+			modifiers: vec![],
+			emitter: EmitterState::new(),
+			buffer: ParticleBuffer::new(),
+			renderer: ParticleRenderer::new(drawable),
+		}
+	}
+
+	/// Creates a new particle system like `new`, but checks the settings
+	/// first (see `ParticleSystemSettings::validate`).
+	pub fn try_new(drawable: D, settings: ParticleSystemSettings) -> Result<Self, WellspringError> {
+		settings.validate()?;
+		Ok(Self::new(drawable, settings))
+	}
+
+	/// Creates a particle system that immediately emits the specified
+	/// number of particles and never emits any more, e.g. for hit
+	/// effects. Use `is_finished` to check when the particle system
+	/// can be thrown away.
+	pub fn one_shot(drawable: D, settings: ParticleSystemSettings, count: usize) -> Self {
+		let mut particle_system = Self::new(drawable, settings);
+		particle_system.stop();
+		particle_system.emit(count);
+		particle_system
+	}
+
+	/// Returns the particle system's emitter.
+	pub fn emitter(&self) -> &EmitterState {
+		&self.emitter
+	}
+
+	/// Returns the particle system's particles.
+	pub fn buffer(&self) -> &ParticleBuffer<D::Drawable> {
+		&self.buffer
+	}
+
+	/// Returns the object that draws the particle system's particles.
+	pub fn renderer(&self) -> &ParticleRenderer<D> {
+		&self.renderer
+	}
+
+	/// Seeds the particle system's random number generator, so
+	/// the particle system behaves the same way every time it's
+	/// seeded with the same number (as long as it's updated with
+	/// the same time steps).
+	pub fn set_seed(&mut self, seed: u64) {
+		self.emitter.set_seed(seed);
+	}
+
+	/// Returns whether the particle emitter is currently creating new particles.
+	pub fn running(&self) -> bool {
+		self.emitter.running()
+	}
+
+	/// Returns how many seconds the particle system has been updated
+	/// for since it was created or restarted.
+	pub fn clock(&self) -> f32 {
+		self.emitter.clock()
+	}
+
+	/// Returns the strength of the particle system, from 0 to 1.
+	pub fn strength(&self) -> f32 {
+		self.emitter.strength()
+	}
+
+	/// Sets the strength of the particle system, from 0 (weakest) to 1
+	/// (strongest). The strength is a single value that can be driven
+	/// by gameplay, like an engine's throttle or a character's health,
+	/// and `ParticleSystemSettings::strength_response` decides how it
+	/// changes the effect. Only new particles are affected by changes
+	/// to their speed and size. Particle systems start at full strength.
+	pub fn set_strength(&mut self, strength: f32) {
+		self.emitter.set_strength(strength);
+	}
+
+	/// Sets the position of the listener, e.g. the camera or the
+	/// player. If the settings have an `activation_radius`, the particle
+	/// system goes to sleep while the listener is farther than that from
+	/// all of its emitters, and wakes up when the listener comes back.
+	pub fn set_listener(&mut self, listener: Point2<f32>) {
+		self.emitter.set_listener(listener);
+	}
+
+	/// Removes the listener, so the particle system is always updated.
+	pub fn clear_listener(&mut self) {
+		self.emitter.clear_listener();
+	}
+
+	/// Returns whether the particle system is asleep because the
+	/// listener is too far away (see `set_listener`). The listener's
+	/// distance is checked when the particle system is updated.
+	pub fn is_asleep(&self) -> bool {
+		self.emitter.is_asleep()
+	}
+
+	/// Estimates the area of the world the particles could cover in
+	/// the next `horizon` seconds, or returns `None` if there are no
+	/// particles and the emitter won't create any. Scene managers can
+	/// use this to start updating or drawing particle systems a bit
+	/// before they come into view.
+	///
+	/// The estimate is based on the particles' current speeds, the
+	/// fastest new particles the emitter can create, and the
+	/// accelerations in the settings (including wind and buoyancy),
+	/// capped by the particles' remaining lifetimes. Forces, attractors,
+	/// colliders, modifiers, and emitter animations aren't taken into
+	/// account, and the bounds cover the particles' positions, not the
+	/// size of what's drawn for them.
+	pub fn predicted_bounds(&self, horizon: f32) -> Option<graphics::Rect> {
+		self.emitter
+			.predicted_bounds(&self.settings, &self.buffer, horizon)
+	}
+
+	/// Returns the number of particles in the system.
+	pub fn count(&self) -> usize {
+		self.buffer.count()
+	}
+
+	/// Reserves room for at least `additional` more particles than the
+	/// system currently has. Reserving room for the largest number of
+	/// particles the system will have up front means the particle
+	/// storage never needs to grow while the system is running.
+	pub fn reserve(&mut self, additional: usize) {
+		self.buffer.reserve(additional);
+		self.emitter.reserve(additional);
+	}
+
+	/// Returns whether the particle emitter is stopped and all of
+	/// the particles have disappeared.
+	pub fn is_finished(&self) -> bool {
+		!self.running() && self.count() == 0
+	}
+
+	/// Starts the particle emitter.
+	pub fn start(&mut self) {
+		self.emitter.start();
+	}
+
+	/// Removes all particles and starts the particle emitter from
+	/// the beginning, including the burst schedule, path, and extra
+	/// emitters. If the particle system is seeded, it plays out the
+	/// same way it did the first time.
+	///
+	/// Particles are numbered from the start again, so IDs of particles
+	/// from before the restart can refer to new particles.
+	pub fn restart(&mut self) {
+		self.emitter.restart(&mut self.buffer);
+	}
+
+	/// Stops the particle emitter. Existing particles are left alone.
+	pub fn stop(&mut self) {
+		self.stop_with(StopMode::EmissionOnly);
+	}
+
+	/// Stops the particle emitter using the given `StopMode`.
+	pub fn stop_with(&mut self, mode: StopMode) {
+		self.emitter.stop_with(mode, &mut self.buffer);
+	}
+
+	/// Calls a function every `n`th time a particle is emitted, e.g.
+	/// to play a sound effect. The function is called with the new
+	/// particle, but no more than once every `min_interval` seconds,
+	/// so a big burst of particles doesn't play hundreds of sounds
+	/// at once.
+	pub fn on_spawn_every_nth<F>(&mut self, n: usize, min_interval: f32, callback: F)
+	where
+		F: Fn(ParticleView) + 'static,
+	{
+		self.emitter.on_spawn_every_nth(n, min_interval, callback);
+	}
+
+	/// Calls a function when a particle hits a collider for the first
+	/// time, e.g. to play a sound effect. The function is called with
+	/// the particle, but no more than once every `min_interval` seconds.
+	pub fn on_first_collision<F>(&mut self, min_interval: f32, callback: F)
+	where
+		F: Fn(ParticleView) + 'static,
+	{
+		self.emitter.on_first_collision(min_interval, callback);
+	}
+
+	/// Removes all of the functions added by `on_spawn_every_nth`
+	/// and `on_first_collision`.
+	pub fn clear_triggers(&mut self) {
+		self.emitter.clear_triggers();
+	}
+
+	/// Adds a force that acts on the particles along with the forces
+	/// in the settings, and returns a handle for changing or removing
+	/// it later. This is useful for forces that change every frame,
+	/// like a magnet that follows the mouse.
+	pub fn add_force(&mut self, force: Force) -> ForceHandle {
+		self.emitter.add_force(force)
+	}
+
+	/// Returns a mutable reference to the force with the given handle,
+	/// or `None` if it was removed.
+	pub fn force_mut(&mut self, handle: ForceHandle) -> Option<&mut Force> {
+		self.emitter.force_mut(handle)
+	}
+
+	/// Moves the force with the given handle. This sets the position
+	/// of attractors, the top-left corner of vector fields, and the
+	/// position of force zones.
+	pub fn set_force_position(&mut self, handle: ForceHandle, position: Point2<f32>) {
+		self.emitter.set_force_position(handle, position);
+	}
+
+	/// Removes the force with the given handle and returns it.
+	pub fn remove_force(&mut self, handle: ForceHandle) -> Option<Force> {
+		self.emitter.remove_force(handle)
+	}
+
+	/// Sets a function to call to draw each particle instead of
+	/// drawing the particle system's drawable.
+	///
+	/// This is useful for drawing particles in ways the particle system
+	/// doesn't support itself, such as setting shader uniforms for
+	/// each particle or adding particles to a sprite batch.
+	///
+	/// The particle system's blend mode isn't applied to what the
+	/// function draws, so set the blend mode of those drawables instead.
+	pub fn set_draw_override<F>(&mut self, draw_override: F)
+	where
+		F: Fn(&mut Context, ParticleView) -> GameResult + 'static,
+	{
+		self.renderer.set_draw_override(draw_override);
+	}
+
+	/// Removes the function set by `set_draw_override`, so particles
+	/// are drawn using the particle system's drawable again.
+	pub fn clear_draw_override(&mut self) {
+		self.renderer.clear_draw_override();
+	}
+
+	/// Makes the emitter move along a line through the given points,
+	/// reaching the end after `duration` seconds. If `looping` is `true`,
+	/// the emitter jumps back to the start of the path after reaching
+	/// the end. The emitter's position setting is updated as it moves.
+	pub fn set_path(&mut self, points: Vec<Point2<f32>>, duration: f32, looping: bool) {
+		self.emitter.set_path(points, duration, looping);
+	}
+
+	/// Stops the emitter from moving along the path set by `set_path`.
+	pub fn clear_path(&mut self) {
+		self.emitter.clear_path();
+	}
+
+	/// Adds an emitter at the given position that emits particles into
+	/// the same pool as the main emitter, using the same settings and
+	/// drawable, and returns its ID. This lets one particle system (and
+	/// one draw call) handle many emitters, e.g. a row of torches along
+	/// a wall.
+	///
+	/// Added emitters emit particles at the emission rate, and the
+	/// particles' positions and directions are relative to the added
+	/// emitter. Each emitter's angle and emission rate can be changed,
+	/// and emitters can be disabled, using `emitter_overrides_mut`.
+	/// Bursts, `emit`, and emitter animations and paths only use the
+	/// main emitter. If the particle system is screen anchored, the
+	/// position is relative to the camera.
+	pub fn add_emitter(&mut self, position: Point2<f32>, shape: EmitterShape) -> EmitterId {
+		self.emitter.add_emitter(position, shape)
+	}
+
+	/// Removes an emitter added with `add_emitter`. Its particles are
+	/// left alone. Returns `false` if the emitter was already removed
+	/// or is the main emitter.
+	pub fn remove_emitter(&mut self, id: EmitterId) -> bool {
+		self.emitter.remove_emitter(id)
+	}
+
+	/// Moves an emitter added with `add_emitter`. Returns `false` if the
+	/// emitter was removed or is the main emitter, which is moved by
+	/// changing `settings.position`.
+	pub fn set_emitter_position(&mut self, id: EmitterId, position: Point2<f32>) -> bool {
+		self.emitter.set_emitter_position(id, position)
+	}
+
+	/// Changes the shape of an emitter added with `add_emitter`. Returns
+	/// `false` if the emitter was removed or is the main emitter, which
+	/// uses `settings.shape`.
+	pub fn set_emitter_shape(&mut self, id: EmitterId, shape: EmitterShape) -> bool {
+		self.emitter.set_emitter_shape(id, shape)
+	}
+
+	/// Returns the IDs of the emitters added with `add_emitter` that
+	/// haven't been removed.
+	pub fn emitters(&self) -> impl Iterator<Item = EmitterId> + '_ {
+		self.emitter.emitters()
+	}
+
+	/// Returns the settings that one of the emitters uses instead of
+	/// the particle system's settings, or `None` if the emitter was
+	/// removed.
+	pub fn emitter_overrides(&self, id: EmitterId) -> Option<&EmitterOverrides> {
+		self.emitter.emitter_overrides(id)
+	}
+
+	/// Returns a mutable reference to the settings that one of the
+	/// emitters uses instead of the particle system's settings, or
+	/// `None` if the emitter was removed. This works for the main
+	/// emitter (`EmitterId::MAIN`) as well, e.g. to disable it when
+	/// all of the particles should come from added emitters.
+	pub fn emitter_overrides_mut(&mut self, id: EmitterId) -> Option<&mut EmitterOverrides> {
+		self.emitter.emitter_overrides_mut(id)
+	}
+
+	/// Sets a shader to use while drawing the particles. If `None`,
+	/// the particles will be drawn with whatever shader is currently
+	/// in use.
+	///
+	/// Shaders share their uniforms with their clones, so a clone of
+	/// the shader can be kept around to update the uniforms later.
+	pub fn set_shader<C>(&mut self, shader: Option<graphics::Shader<C>>)
+	where
+		C: 'static + gfx::pso::buffer::Structure<gfx::shade::ConstFormat>,
+	{
+		self.renderer.set_shader(shader);
+	}
+
+	/// Makes the particle system draw its particles onto a canvas the
+	/// size of the window, which is then drawn to the screen all at once
+	/// using the `DrawParam` the particle system is drawn with.
+	///
+	/// This allows fading the whole effect in or out using the
+	/// `DrawParam`'s color, and it keeps overlapping translucent particles
+	/// from blending with what's behind the effect separately. Call this
+	/// again after the window is resized to resize the canvas.
+	///
+	/// Note that the particle system will set the render target back to
+	/// the screen after drawing to the canvas.
+	pub fn enable_canvas(&mut self, ctx: &mut Context) -> Result<(), WellspringError> {
+		self.renderer.enable_canvas(ctx)
+	}
+
+	/// Makes the particle system draw its particles directly to the
+	/// current render target again.
+	pub fn disable_canvas(&mut self) {
+		self.renderer.disable_canvas();
+	}
+
+	/// Immediately emits the specified number of particles and returns
+	/// the IDs of the new particles.
+	///
+	/// Each particle's direction is picked separately using the angle
+	/// and spread settings. Use `emit_uniform` to send every particle
+	/// the same way.
+	///
+	/// If the system has the maximum number of particles, fewer particles
+	/// may be emitted than requested, depending on the overflow policy.
+	pub fn emit(&mut self, count: usize) -> Vec<ParticleId> {
+		self.emitter.emit(&self.settings, &mut self.buffer, count)
+	}
+
+	/// Immediately emits the specified number of particles, all moving
+	/// in the same direction, and returns the IDs of the new particles.
+	/// The direction is picked once using the angle and spread settings,
+	/// and everything else (including speed) is still picked separately
+	/// for each particle.
+	pub fn emit_uniform(&mut self, count: usize) -> Vec<ParticleId> {
+		self.emitter
+			.emit_uniform(&self.settings, &mut self.buffer, count)
+	}
+
+	/// Immediately emits a single particle and returns its ID, or `None`
+	/// if the particle couldn't be emitted because the system has the
+	/// maximum number of particles.
+	pub fn emit_one(&mut self) -> Option<ParticleId> {
+		self.emitter.emit_one(&self.settings, &mut self.buffer)
 	}
 
-	/// Stops the particle emitter.
-	pub fn stop(&mut self) {
-		self.running = false;
+	/// Immediately emits one particle at each of the given points
+	/// (instead of in the emitter's shape) and returns the IDs of
+	/// the new particles. The particles' directions and other
+	/// parameters are chosen as usual.
+	pub fn emit_from_points(
+		&mut self,
+		points: impl IntoIterator<Item = Point2<f32>>,
+	) -> Vec<ParticleId> {
+		self.emitter
+			.emit_from_points(&self.settings, &mut self.buffer, points)
 	}
 
-	fn get_particle_position_offset(
-		emitter_shape: &EmitterShape,
-		rng: &mut ThreadRng,
-	) -> Vector2<f32> {
-		match emitter_shape {
-			EmitterShape::Point => Vector2::new(0.0, 0.0),
-			EmitterShape::Rectangle(size, angle) => {
-				Rotation2::new(*angle)
-					* Vector2::new(
-						lerp(-size.x / 2.0, size.x / 2.0, rng.gen::<f32>()),
-						lerp(-size.y / 2.0, size.y / 2.0, rng.gen::<f32>()),
-					)
-			}
-			EmitterShape::Ellipse(size, angle) => {
-				let particle_angle = 2.0 * std::f32::consts::PI * rng.gen::<f32>();
-				let distance = rng.gen::<f32>();
-				Rotation2::new(*angle)
-					* Vector2::new(
-						distance * particle_angle.cos() * size.x,
-						distance * particle_angle.sin() * size.y,
-					)
-			}
-			EmitterShape::RectangleBorder(size, angle) => {
-				let top_left = Vector2::new(-size.x / 2.0, -size.y / 2.0);
-				let top_right = Vector2::new(size.x / 2.0, -size.y / 2.0);
-				let bottom_right = Vector2::new(size.x / 2.0, size.y / 2.0);
-				let bottom_left = Vector2::new(-size.x / 2.0, size.y / 2.0);
-				let side_boundaries = [
-					size.x,
-					size.x + size.y,
-					size.x * 2.0 + size.y,
-					size.x * 2.0 + size.y * 2.0,
-				];
-				let amount = side_boundaries[3] * rng.gen::<f32>();
-				let offset = if amount > side_boundaries[2] {
-					lerp(
-						bottom_left,
-						top_left,
-						(amount - side_boundaries[2]) / (side_boundaries[3] - side_boundaries[2]),
-					)
-				} else if amount > side_boundaries[1] {
-					lerp(
-						bottom_right,
-						bottom_left,
-						(amount - side_boundaries[1]) / (side_boundaries[2] - side_boundaries[1]),
-					)
-				} else if amount > side_boundaries[0] {
-					lerp(
-						top_right,
-						bottom_right,
-						(amount - side_boundaries[0]) / (side_boundaries[1] - side_boundaries[0]),
-					)
-				} else {
-					lerp(top_left, top_right, amount / side_boundaries[0])
-				};
-				Rotation2::new(*angle) * offset
-			}
-			EmitterShape::EllipseBorder(size, angle) => {
-				let particle_angle = 2.0 * std::f32::consts::PI * rng.gen::<f32>();
-				Rotation2::new(*angle)
-					* Vector2::new(particle_angle.cos() * size.x, particle_angle.sin() * size.y)
-			}
-		}
+	/// Immediately emits the given number of particles at a point.
+	/// Unlike `emit_from_points`, this doesn't return the IDs of the
+	/// new particles, so it doesn't need to allocate a list for them.
+	pub fn emit_at_point(&mut self, point: Point2<f32>, count: usize) {
+		self.emitter
+			.emit_at_point(&self.settings, &mut self.buffer, point, count)
 	}
 
-	/// Immediately emits the specified number of particles.
-	pub fn emit(&mut self, count: usize) {
-		for _ in 0..count {
-			let angle = lerp(
-				self.settings.angle - self.settings.spread / 2.0,
-				self.settings.angle + self.settings.spread / 2.0,
-				self.rng.gen::<f32>(),
-			);
-			let speed = get_rand_in_range(&self.settings.speed, &mut self.rng);
-			let velocity = Vector2::new(speed * angle.cos(), speed * angle.sin());
-			let position = self.settings.position
-				+ Self::get_particle_position_offset(&self.settings.shape, &mut self.rng);
-			self.particles.push(Particle {
-				sizes: self.settings.sizes.clone(),
-				colors: self.settings.colors.clone(),
-				lifetime: get_rand_in_range(&self.settings.particle_lifetime, &mut self.rng),
-				time: 0.0,
-				position,
-				velocity,
-				damping: get_rand_in_range(&self.settings.damping, &mut self.rng),
-				acceleration: get_rand_in_range(&self.settings.acceleration, &mut self.rng),
-				radial_acceleration: get_rand_in_range(
-					&self.settings.radial_acceleration,
-					&mut self.rng,
-				),
-				tangential_acceleration: get_rand_in_range(
-					&self.settings.tangential_acceleration,
-					&mut self.rng,
-				),
-				angle: 0.0,
-				spin: get_rand_in_range(&self.settings.spin, &mut self.rng),
-				use_relative_angle: self.settings.use_relative_angle,
-				offset: self.settings.offset,
-			});
-		}
+	/// Immediately emits a burst of particles in a pattern, such as a
+	/// ring, and returns the IDs of the new particles. The pattern
+	/// replaces the emitter's shape and/or the angle and spread
+	/// settings for this burst only, so a general-purpose particle
+	/// system can fire shaped bursts at scripted moments.
+	pub fn burst(&mut self, pattern: BurstPattern) -> Vec<ParticleId> {
+		self.emitter
+			.burst(&self.settings, &mut self.buffer, pattern)
+	}
+
+	/// Returns whether the particle with the given ID is still alive.
+	pub fn is_alive(&self, id: ParticleId) -> bool {
+		self.buffer.is_alive(id)
+	}
+
+	/// Returns the current state of the particle with the given ID,
+	/// or `None` if the particle is no longer alive.
+	pub fn get(&self, id: ParticleId) -> Option<ParticleView> {
+		self.buffer.get(id)
+	}
+
+	/// Returns the IDs and current states of all the particles,
+	/// from oldest to newest.
+	pub fn particles(&self) -> impl Iterator<Item = (ParticleId, ParticleView)> + '_ {
+		self.buffer.particles()
+	}
+
+	/// Returns a light for each particle, so 2D lighting systems can make
+	/// effects like fire light up the scene. The lights' sizes, colors,
+	/// and brightness come from the particles and the `light_mapping`
+	/// setting.
+	pub fn light_sources(&self) -> impl Iterator<Item = LightSample> + '_ {
+		self.buffer.light_sources(&self.settings.light_mapping)
+	}
+
+	/// Immediately removes the particle with the given ID. Returns
+	/// `false` if the particle was already dead.
+	pub fn kill(&mut self, id: ParticleId) -> bool {
+		self.buffer.kill(id)
+	}
+
+	/// Returns the draw parameters for each particle in the system
+	/// without drawing anything.
+	///
+	/// This is useful for drawing particles with a custom renderer,
+	/// such as a `graphics::spritebatch::SpriteBatch`.
+	pub fn instance_params(&self) -> impl Iterator<Item = graphics::DrawParam> + '_ {
+		self.renderer.instance_params(&self.buffer)
+	}
+
+	/// Draws the particle system as seen by a camera, skipping
+	/// particles that are outside of the camera's view.
+	///
+	/// `camera` is the area of the world the camera can see. `parallax`
+	/// controls how much the particles move with the camera: `1.0` for
+	/// effects in the same layer as the camera, less than `1.0` for
+	/// effects in the background, and more than `1.0` for effects
+	/// in the foreground.
+	pub fn draw_with_camera(
+		&self,
+		ctx: &mut Context,
+		camera: graphics::Rect,
+		parallax: f32,
+	) -> GameResult {
+		self.renderer
+			.draw_with_camera(ctx, &self.settings, &self.buffer, camera, parallax)
 	}
 
 	/// Updates the particle emitter and the individual particles in the system.
 	pub fn update(&mut self, ctx: &Context) {
-		let delta_time = ggez::timer::delta(ctx).as_secs_f32();
-		// emit new particles
-		if self.running {
-			self.emit_timer -= self.settings.emission_rate * delta_time;
-			while self.emit_timer <= 0.0 {
-				self.emit_timer += 1.0;
-				self.emit(1);
-			}
-			self.time += delta_time;
-			if let EmitterLifetime::Finite(time) = self.settings.emitter_lifetime {
-				if self.time >= time {
-					self.stop();
-				}
-			}
-		}
-		// update existing particles
-		for i in (0..self.particles.len()).rev() {
-			let particle = &mut self.particles[i];
-			particle.update(ctx, self.settings.position);
-			if particle.time >= 1.0 {
-				self.particles.remove(i);
+		self.advance(ggez::timer::delta(ctx).as_secs_f32());
+	}
+
+	/// Updates the particle emitter and the individual particles in the
+	/// system by the given number of seconds. This is useful for updating
+	/// particle systems with a fixed time step.
+	pub fn advance(&mut self, delta_time: f32) {
+		self.check_allocations(|system| {
+			system.emitter.advance(
+				&mut system.settings,
+				&mut system.modifiers,
+				&mut system.buffer,
+				delta_time,
+			)
+		});
+	}
+
+	/// Runs an update. With the `allocation_checks` feature, this
+	/// panics if the update allocates memory once the particle system
+	/// has warmed up, i.e. it's been running long enough for the
+	/// number of particles to level off. Allocations are counted by
+	/// `testing::CountingAllocator`.
+	fn check_allocations<F>(&mut self, update: F)
+	where
+		F: FnOnce(&mut Self),
+	{
+		#[cfg(feature = "allocation_checks")]
+		{
+			let warm_up =
+				get_max_magnitude(&self.settings.particle_lifetime) + ALLOCATION_CHECK_WARM_UP;
+			if self.emitter.clock >= warm_up {
+				let before = testing::allocation_count();
+				update(self);
+				let allocations = testing::allocation_count() - before;
+				assert!(
+					allocations == 0,
+					"particle system allocated memory {} times during an update after warming up \
+					 (use `reserve` to make room for all of the particles up front)",
+					allocations
+				);
+				return;
 			}
 		}
+		update(self)
+	}
+
+	/// Updates the particle emitter, then updates as many particles as
+	/// possible in `max_micros` microseconds. See `advance_budgeted`.
+	pub fn update_budgeted(&mut self, ctx: &Context, max_micros: u64) {
+		self.advance_budgeted(
+			ggez::timer::delta(ctx).as_secs_f32(),
+			Duration::from_micros(max_micros),
+		);
+	}
+
+	/// Updates the particle emitter, then updates as many particles as
+	/// possible within the time budget. The next update picks up where
+	/// this one left off, and particles that were skipped catch up on
+	/// the time they missed, so huge background effects move slightly
+	/// less smoothly instead of slowing down the game.
+	///
+	/// Particles that are behind don't catch up if the particle system
+	/// is updated normally with `advance` or `update`. Like `advance`,
+	/// this only simulates the particles once every `update_interval`
+	/// if the settings have one.
+	pub fn advance_budgeted(&mut self, delta_time: f32, budget: Duration) {
+		self.check_allocations(|system| {
+			system.emitter.advance_budgeted(
+				&mut system.settings,
+				&mut system.modifiers,
+				&mut system.buffer,
+				delta_time,
+				budget,
+			)
+		});
+	}
+
+	/// Returns and clears the requests from sub-emitters to emit
+	/// particles into other particle systems. `ParticleSystemSet`
+	/// handles these automatically.
+	pub fn take_sub_emissions(&mut self) -> Vec<SubEmission> {
+		self.emitter.take_sub_emissions()
+	}
+
+	/// Moves the requests from sub-emitters to emit particles into
+	/// other particle systems onto the end of a list. See
+	/// `EmitterState::drain_sub_emissions`.
+	pub fn drain_sub_emissions(&mut self, sub_emissions: &mut Vec<SubEmission>) {
+		self.emitter.drain_sub_emissions(sub_emissions)
+	}
+
+	/// Returns the IDs and current states of all the particles
+	/// within a rectangle.
+	pub fn query_rect(&self, rect: graphics::Rect) -> Vec<(ParticleId, ParticleView)> {
+		self.buffer.query_rect(rect)
+	}
+
+	/// Returns the IDs and current states of all the particles
+	/// within a circle.
+	pub fn query_circle(
+		&self,
+		center: Point2<f32>,
+		radius: f32,
+	) -> Vec<(ParticleId, ParticleView)> {
+		self.buffer.query_circle(center, radius)
+	}
+
+	/// Adds to the velocity of every particle in a region, e.g. to blow
+	/// smoke away from an explosion.
+	pub fn apply_impulse(&mut self, region: Region, impulse: Vector2<f32>) {
+		self.buffer.apply_impulse(region, impulse);
+	}
+
+	/// Sets how fast time passes for the particle with the given ID,
+	/// e.g. `0.0` to freeze it for a hit-stop effect. This affects how
+	/// quickly the particle ages, moves, and spins, and it lasts until
+	/// it's changed again. Modifiers can change the time scale further
+	/// (see `ParticleState::time_scale`). Returns `false` if the
+	/// particle is dead.
+	pub fn set_time_scale(&mut self, id: ParticleId, time_scale: f32) -> bool {
+		self.buffer.set_time_scale(id, time_scale)
+	}
+
+	/// Sets how fast time passes for every particle that matches a
+	/// condition. See `set_time_scale`.
+	pub fn set_time_scale_where<F>(&mut self, predicate: F, time_scale: f32)
+	where
+		F: FnMut(&ParticleView) -> bool,
+	{
+		self.buffer.set_time_scale_where(predicate, time_scale);
+	}
+
+	/// Gives every particle in a region more time to live (in seconds),
+	/// e.g. so embers in an updraft burn longer. The particles' size
+	/// and color keyframes are stretched over the extra time. Negative
+	/// times shorten the particles' lifetimes instead.
+	pub fn extend_lifetime(&mut self, region: Region, extra_time: f32) {
+		self.buffer.extend_lifetime(region, extra_time);
+	}
+
+	/// Gives every particle that matches a condition more time to
+	/// live (in seconds). See `extend_lifetime`.
+	pub fn extend_lifetime_where<F>(&mut self, predicate: F, extra_time: f32)
+	where
+		F: FnMut(&ParticleView) -> bool,
+	{
+		self.buffer.extend_lifetime_where(predicate, extra_time);
+	}
+
+	/// Updates the particle system as seen by a camera, where `camera` is
+	/// the area of the world the camera can see.
+	///
+	/// If the particle system is screen anchored, the emitter follows the
+	/// camera, and particles that leave the camera's view wrap around to
+	/// the opposite edge. The camera is also used by `emit` until the
+	/// next time this is called.
+	pub fn update_with_camera(&mut self, ctx: &Context, camera: graphics::Rect) {
+		self.emitter.set_camera(Some(camera));
+		self.update(ctx);
 	}
 }
 
-impl<D> graphics::Drawable for ParticleSystem<D>
+impl<D> Clone for ParticleSystem<D>
 where
-	D: graphics::Drawable,
+	D: DrawableProvider + Clone,
+	D::Drawable: Clone,
 {
-	fn draw(&self, ctx: &mut Context, _param: graphics::DrawParam) -> GameResult {
-		for particle in &self.particles {
-			particle.draw(ctx, &self.drawable)?;
+	fn clone(&self) -> Self {
+		Self {
+			settings: self.settings.clone(),
+			modifiers: self.modifiers.clone(),
+			emitter: self.emitter.clone(),
+			buffer: self.buffer.clone(),
+			renderer: self.renderer.clone(),
 		}
+	}
+}
+
+impl<D> std::fmt::Debug for ParticleSystem<D>
+where
+	D: DrawableProvider,
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ParticleSystem")
+			.field("count", &self.count())
+			.field("position", &self.settings.position)
+			.field("modifiers", &self.modifiers.len())
+			.field("emitter", &self.emitter)
+			.field("buffer", &self.buffer)
+			.field("renderer", &self.renderer)
+			.finish()
+	}
+}
+
+/// A drawable that draws nothing, used for particle systems that
+/// are simulated without a graphics context.
+#[derive(Clone)]
+struct Headless;
+
+impl graphics::Drawable for Headless {
+	fn draw(&self, _ctx: &mut Context, _param: graphics::DrawParam) -> GameResult {
 		Ok(())
 	}
 
@@ -493,3 +5153,750 @@ where
 		None
 	}
 }
+
+/// Runs a particle system with the given settings and seed for
+/// `steps` updates of `delta_time` seconds each and returns the
+/// state of the remaining particles.
+///
+/// This doesn't need a `Context`, so it can be used for testing
+/// effects or running them on a headless server.
+pub fn simulate(
+	settings: ParticleSystemSettings,
+	seed: u64,
+	delta_time: f32,
+	steps: usize,
+) -> Vec<ParticleView> {
+	let mut particle_system = ParticleSystem::new(Headless, settings);
+	particle_system.set_seed(seed);
+	for _ in 0..steps {
+		particle_system.advance(delta_time);
+	}
+	particle_system.particles().map(|(_, view)| view).collect()
+}
+
+impl ParticleSystem<graphics::Text> {
+	/// Immediately emits the specified number of particles, each
+	/// displaying its own text instead of the particle system's text.
+	///
+	/// `text` is called once for each new particle with the index
+	/// of that particle within the burst. Returns the IDs of the
+	/// new particles.
+	pub fn emit_text<F>(&mut self, count: usize, text: F) -> Vec<ParticleId>
+	where
+		F: FnMut(usize) -> graphics::Text,
+	{
+		self.emitter
+			.emit_text(&self.settings, &mut self.buffer, count, text)
+	}
+}
+
+impl ParticleSystem<graphics::Image> {
+	/// Draws all of the particles with a single draw call using a
+	/// `graphics::spritebatch::SpriteBatch`, which is much faster than
+	/// drawing each particle individually when there are many particles.
+	///
+	/// The render mode and draw override are ignored when drawing
+	/// particles this way. Particle systems that use meshes can be
+	/// batched too if the mesh is a `ParticleMesh`.
+	pub fn draw_batched(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		self.renderer.draw_batched(ctx, &self.buffer, param)
+	}
+}
+
+impl ParticleSystem<ParticleMesh> {
+	/// Draws all of the particles with a single draw call by combining
+	/// a copy of the mesh for each particle into one mesh, which is
+	/// much faster than drawing each particle individually when there
+	/// are thousands of simple particles like rectangles and circles.
+	///
+	/// The render mode and draw override are ignored when drawing
+	/// particles this way.
+	pub fn draw_batched(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		self.renderer.draw_batched(ctx, &self.buffer, param)
+	}
+}
+
+impl<D> graphics::Drawable for ParticleSystem<D>
+where
+	D: DrawableProvider,
+{
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		self.renderer.draw(ctx, &self.settings, &self.buffer, param)
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		None
+	}
+
+	/// Sets the blend mode of the particle system's drawable (or each
+	/// of the drawables of its `DrawableProvider`). Particles that
+	/// have their own drawable, like the ones emitted by `emit_text`,
+	/// keep their own blend mode. The blend mode is also used for
+	/// batched drawing and for drawing the canvas (see `enable_canvas`),
+	/// but not for particles drawn with a draw override.
+	fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		self.renderer.set_blend_mode(mode);
+	}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		self.renderer.blend_mode()
+	}
+}
+
+/// A particle system with the type of its drawable erased.
+///
+/// Every `ParticleSystem` implements this trait, so particle systems
+/// using different kinds of drawables (for example, meshes, images,
+/// and text) can be stored together as `Box<dyn AnyParticleSystem>`.
+pub trait AnyParticleSystem: graphics::Drawable {
+	/// Returns the settings defining how the particle system behaves.
+	fn settings(&self) -> &ParticleSystemSettings;
+
+	/// Returns a mutable reference to the settings defining how the
+	/// particle system behaves.
+	fn settings_mut(&mut self) -> &mut ParticleSystemSettings;
+
+	/// Returns whether the particle emitter is currently creating new particles.
+	fn running(&self) -> bool;
+
+	/// Returns the number of particles in the system.
+	fn count(&self) -> usize;
+
+	/// Returns whether the particle emitter is stopped and all of
+	/// the particles have disappeared.
+	fn is_finished(&self) -> bool;
+
+	/// Starts the particle emitter.
+	fn start(&mut self);
+
+	/// Stops the particle emitter.
+	fn stop(&mut self);
+
+	/// Immediately emits the specified number of particles and returns
+	/// the IDs of the new particles.
+	fn emit(&mut self, count: usize) -> Vec<ParticleId>;
+
+	/// Immediately emits one particle at each of the given points
+	/// and returns the IDs of the new particles.
+	fn emit_from_points(&mut self, points: &[Point2<f32>]) -> Vec<ParticleId>;
+
+	/// Immediately emits the given number of particles at a point.
+	fn emit_at_point(&mut self, point: Point2<f32>, count: usize);
+
+	/// Updates the particle emitter and the individual particles in the system.
+	fn update(&mut self, ctx: &Context);
+
+	/// Updates the particle emitter and the individual particles in the
+	/// system by the given number of seconds.
+	fn advance(&mut self, delta_time: f32);
+
+	/// Returns and clears the requests from sub-emitters to emit
+	/// particles into other particle systems.
+	fn take_sub_emissions(&mut self) -> Vec<SubEmission>;
+
+	/// Moves the requests from sub-emitters to emit particles into
+	/// other particle systems onto the end of a list.
+	fn drain_sub_emissions(&mut self, sub_emissions: &mut Vec<SubEmission>);
+}
+
+impl<D> AnyParticleSystem for ParticleSystem<D>
+where
+	D: DrawableProvider,
+{
+	fn settings(&self) -> &ParticleSystemSettings {
+		&self.settings
+	}
+
+	fn settings_mut(&mut self) -> &mut ParticleSystemSettings {
+		&mut self.settings
+	}
+
+	fn running(&self) -> bool {
+		ParticleSystem::running(self)
+	}
+
+	fn count(&self) -> usize {
+		ParticleSystem::count(self)
+	}
+
+	fn is_finished(&self) -> bool {
+		ParticleSystem::is_finished(self)
+	}
+
+	fn start(&mut self) {
+		ParticleSystem::start(self)
+	}
+
+	fn stop(&mut self) {
+		ParticleSystem::stop(self)
+	}
+
+	fn emit(&mut self, count: usize) -> Vec<ParticleId> {
+		ParticleSystem::emit(self, count)
+	}
+
+	fn emit_from_points(&mut self, points: &[Point2<f32>]) -> Vec<ParticleId> {
+		ParticleSystem::emit_from_points(self, points.iter().copied())
+	}
+
+	fn emit_at_point(&mut self, point: Point2<f32>, count: usize) {
+		ParticleSystem::emit_at_point(self, point, count)
+	}
+
+	fn update(&mut self, ctx: &Context) {
+		ParticleSystem::update(self, ctx)
+	}
+
+	fn advance(&mut self, delta_time: f32) {
+		ParticleSystem::advance(self, delta_time)
+	}
+
+	fn take_sub_emissions(&mut self) -> Vec<SubEmission> {
+		ParticleSystem::take_sub_emissions(self)
+	}
+
+	fn drain_sub_emissions(&mut self, sub_emissions: &mut Vec<SubEmission>) {
+		ParticleSystem::drain_sub_emissions(self, sub_emissions)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn headless_system(settings: ParticleSystemSettings) -> ParticleSystem<Headless> {
+		let mut particle_system = ParticleSystem::new(Headless, settings);
+		particle_system.set_seed(1234);
+		particle_system
+	}
+
+	fn views(particle_system: &ParticleSystem<Headless>) -> Vec<ParticleView> {
+		particle_system.particles().map(|(_, view)| view).collect()
+	}
+
+	fn directions(particle_system: &ParticleSystem<Headless>) -> Vec<f32> {
+		views(particle_system)
+			.iter()
+			.map(|view| view.velocity.y.atan2(view.velocity.x))
+			.collect()
+	}
+
+	fn positions(particles: &[ParticleView]) -> Vec<Point2<f32>> {
+		particles.iter().map(|view| view.position).collect()
+	}
+
+	#[test]
+	fn emit_picks_a_direction_for_each_particle() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			..Default::default()
+		});
+		particle_system.emit(10);
+		let directions = directions(&particle_system);
+		assert_eq!(directions.len(), 10);
+		assert!(directions
+			.iter()
+			.any(|&direction| (direction - directions[0]).abs() > 1e-3));
+	}
+
+	#[test]
+	fn emit_uniform_shares_one_direction() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			..Default::default()
+		});
+		particle_system.emit_uniform(10);
+		let directions = directions(&particle_system);
+		assert_eq!(directions.len(), 10);
+		assert!(directions
+			.iter()
+			.all(|&direction| (direction - directions[0]).abs() < 1e-5));
+	}
+
+	#[test]
+	fn same_seed_gives_same_snapshot() {
+		let settings = || ParticleSystemSettings {
+			emission_rate: 60.0,
+			shape: EmitterShape::Rectangle(Vector2::new(100.0, 50.0), 0.0),
+			particle_lifetime: 0.5..2.0,
+			colors: vec![graphics::WHITE, graphics::BLACK].into(),
+			..Default::default()
+		};
+		let first = testing::snapshot(settings(), 1234, 1.0 / 60.0, 120);
+		let second = testing::snapshot(settings(), 1234, 1.0 / 60.0, 120);
+		assert!(first.count() > 0);
+		assert_eq!(first, second);
+		assert_ne!(first, testing::snapshot(settings(), 4321, 1.0 / 60.0, 120));
+	}
+
+	#[test]
+	fn changing_one_parameter_keeps_the_others() {
+		let settings = |sizes: Vec<f32>| ParticleSystemSettings {
+			emission_rate: 60.0,
+			shape: EmitterShape::Rectangle(Vector2::new(100.0, 50.0), 0.0),
+			sizes: sizes.into(),
+			..Default::default()
+		};
+		let small = simulate(settings(vec![1.0]), 1234, 1.0 / 60.0, 60);
+		let large = simulate(settings(vec![4.0]), 1234, 1.0 / 60.0, 60);
+		assert_eq!(positions(&small), positions(&large));
+	}
+
+	#[test]
+	fn kill_oldest_triggers_death_sub_emitters() {
+		let mut set = ParticleSystemSet::new();
+		let target = set.add(headless_system(Default::default()));
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			max_particles: Some(5),
+			overflow_policy: OverflowPolicy::KillOldest,
+			sub_emitters: vec![SubEmitter {
+				trigger: SubEmitterTrigger::Death,
+				count: 2,
+				target: Some(target),
+			}],
+			..Default::default()
+		});
+		particle_system.emit(8);
+		assert_eq!(particle_system.count(), 5);
+		let sub_emissions = particle_system.take_sub_emissions();
+		assert_eq!(sub_emissions.len(), 3);
+		assert!(sub_emissions
+			.iter()
+			.all(|sub_emission| sub_emission.count == 2));
+	}
+
+	#[test]
+	fn fade_oldest_fades_one_particle_per_extra_particle() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			particle_lifetime: 10.0..10.0,
+			max_particles: Some(5),
+			overflow_policy: OverflowPolicy::FadeOldest(0.1),
+			..Default::default()
+		});
+		particle_system.emit(8);
+		assert_eq!(particle_system.count(), 8);
+		// particles are always drawn once before they die
+		particle_system.advance(0.2);
+		particle_system.advance(0.2);
+		assert_eq!(particle_system.count(), 5);
+		// the particles that faded out no longer count towards the limit
+		particle_system.emit(2);
+		particle_system.advance(0.2);
+		assert_eq!(particle_system.count(), 5);
+	}
+
+	#[test]
+	fn reversed_ranges_sample_between_the_ends() {
+		let range = ValueRange::from(200.0..100.0);
+		assert_eq!(range, ValueRange::new(100.0, 200.0));
+		assert_eq!(range.sample(0.0), 100.0);
+		assert_eq!(range.sample(1.0), 200.0);
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			speed: 200.0..100.0,
+			particle_lifetime: 3.0..2.0,
+			..Default::default()
+		});
+		particle_system.emit(100);
+		for (_, view) in particle_system.particles() {
+			let speed = view.velocity.norm();
+			assert!((100.0 - 1e-3..=200.0 + 1e-3).contains(&speed));
+			assert!(ValueRange::new(2.0, 3.0).contains(view.lifetime));
+		}
+	}
+
+	#[test]
+	fn curves_hit_keyframes_at_segment_boundaries() {
+		let easings = [
+			Easing::Linear,
+			Easing::Step,
+			Easing::EaseIn,
+			Easing::EaseOut,
+			Easing::EaseInOut,
+			Easing::CubicIn,
+			Easing::CubicOut,
+			Easing::CubicInOut,
+			Easing::ExpoIn,
+			Easing::ExpoOut,
+			Easing::BackIn,
+			Easing::BackOut,
+			Easing::ElasticOut,
+			Easing::BounceOut,
+		];
+		for &easing in &easings {
+			assert!(easing.apply(0.0).abs() < 1e-5, "{:?}", easing);
+			if easing != Easing::Step {
+				assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+			}
+			let curve = Curve::new(vec![0.0, 10.0, 20.0]).with_easing(easing);
+			for &(time, value) in &[(0.0, 0.0), (0.5, 10.0), (1.0, 20.0)] {
+				let result = curve.evaluate(time).unwrap();
+				assert!((result - value).abs() < 1e-4, "{:?} at {}", easing, time);
+			}
+		}
+		let curve = Curve::new(vec![0.0, 10.0, 20.0]).with_segment_easing(0, Easing::Step);
+		assert_eq!(curve.evaluate(0.49), Some(0.0));
+		assert_eq!(curve.evaluate(0.5), Some(10.0));
+		assert_eq!(curve.evaluate(0.75), Some(15.0));
+		assert_eq!(Curve::<f32>::default().evaluate(0.5), None);
+	}
+
+	#[test]
+	fn budgeted_updates_catch_up() {
+		let settings = ParticleSystemSettings {
+			emission_rate: 0.0,
+			particle_lifetime: 10.0..10.0,
+			..Default::default()
+		};
+		let mut normal = headless_system(settings.clone());
+		let mut budgeted = headless_system(settings);
+		normal.emit(200);
+		budgeted.emit(200);
+		for _ in 0..10 {
+			normal.advance(1.0 / 60.0);
+			// with no time to spare, only a few particles are updated
+			budgeted.advance_budgeted(1.0 / 60.0, Duration::from_secs(0));
+		}
+		assert_ne!(positions(&views(&normal)), positions(&views(&budgeted)));
+		normal.advance(1.0 / 60.0);
+		budgeted.advance_budgeted(1.0 / 60.0, Duration::from_secs(60));
+		for (a, b) in views(&normal).iter().zip(views(&budgeted).iter()) {
+			assert!((a.position - b.position).norm() < 1e-3);
+		}
+	}
+
+	#[test]
+	fn sub_frame_particles_survive_one_update() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			particle_lifetime: 0.001..0.001,
+			..Default::default()
+		});
+		particle_system.emit(1);
+		particle_system.advance(1.0 / 60.0);
+		assert_eq!(particle_system.count(), 1);
+		particle_system.advance(1.0 / 60.0);
+		assert_eq!(particle_system.count(), 0);
+	}
+
+	#[test]
+	fn killed_sub_frame_particles_stay_dead() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			particle_lifetime: 0.001..0.001,
+			speed: 0.0..0.0,
+			world_bounds: Some((
+				graphics::Rect::new(0.0, 0.0, 100.0, 100.0),
+				BoundsBehavior::Kill,
+			)),
+			..Default::default()
+		});
+		// a sub-frame particle inside the bounds survives its first
+		// update, but one outside of them is killed right away
+		particle_system.emit_at_point(Point2::new(50.0, 50.0), 1);
+		particle_system.emit_at_point(Point2::new(500.0, 50.0), 1);
+		particle_system.advance(1.0 / 60.0);
+		assert_eq!(
+			positions(&views(&particle_system)),
+			[Point2::new(50.0, 50.0)]
+		);
+	}
+
+	#[cfg(feature = "serialization")]
+	fn settings_with_colors_and_rects() -> ParticleSystemSettings {
+		ParticleSystemSettings {
+			colors: Gradient::new(vec![
+				Color::new(1.0, 0.5, 0.25, 1.0),
+				Color::new(0.0, 0.0, 1.0, 0.0),
+			]),
+			world_bounds: Some((
+				graphics::Rect::new(10.0, 20.0, 300.0, 400.0),
+				BoundsBehavior::Bounce,
+			)),
+			forces: vec![Force::Zone(ForceZone {
+				region: Region::Rect(graphics::Rect::new(-5.0, -5.0, 10.0, 10.0)),
+				falloff: 0.0,
+				force: Vector2::new(0.0, 50.0),
+				drag: 0.0,
+			})],
+			..Default::default()
+		}
+	}
+
+	#[cfg(feature = "serialization")]
+	fn assert_colors_and_rects_survived(settings: &ParticleSystemSettings) {
+		assert_eq!(
+			settings.colors.colors(),
+			&[
+				Color::new(1.0, 0.5, 0.25, 1.0),
+				Color::new(0.0, 0.0, 1.0, 0.0)
+			]
+		);
+		match &settings.world_bounds {
+			Some((rect, BoundsBehavior::Bounce)) => {
+				assert_eq!(*rect, graphics::Rect::new(10.0, 20.0, 300.0, 400.0))
+			}
+			bounds => panic!("unexpected world bounds: {:?}", bounds),
+		}
+		match settings.forces.as_slice() {
+			[Force::Zone(ForceZone {
+				region: Region::Rect(rect),
+				..
+			})] => assert_eq!(*rect, graphics::Rect::new(-5.0, -5.0, 10.0, 10.0)),
+			forces => panic!("unexpected forces: {:?}", forces),
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "serialization")]
+	fn colors_and_rects_round_trip_through_ron() {
+		let ron = settings_with_colors_and_rects().to_ron_pretty().unwrap();
+		let settings = ParticleSystemSettings::from_ron(&ron).unwrap();
+		assert_colors_and_rects_survived(&settings);
+		assert_eq!(settings.to_ron_pretty().unwrap(), ron);
+	}
+
+	#[test]
+	#[cfg(feature = "serialization")]
+	fn colors_and_rects_round_trip_through_json() {
+		let json = settings_with_colors_and_rects().to_json_pretty().unwrap();
+		let settings = ParticleSystemSettings::from_json(&json).unwrap();
+		assert_colors_and_rects_survived(&settings);
+		assert_eq!(settings.to_json_pretty().unwrap(), json);
+	}
+
+	#[test]
+	fn validate_rejects_unusable_mass_opacity_and_spin() {
+		let check = |settings: ParticleSystemSettings| settings.validate().is_err();
+		assert!(!check(ParticleSystemSettings::default()));
+		assert!(check(ParticleSystemSettings {
+			mass: 0.0..1.0,
+			..Default::default()
+		}));
+		assert!(check(ParticleSystemSettings {
+			mass: 1.0..-2.0,
+			..Default::default()
+		}));
+		assert!(check(ParticleSystemSettings {
+			mass: 1.0..f32::INFINITY,
+			..Default::default()
+		}));
+		assert!(check(ParticleSystemSettings {
+			opacity: f32::NAN..1.0,
+			..Default::default()
+		}));
+		assert!(check(ParticleSystemSettings {
+			opacity: -0.5..1.0,
+			..Default::default()
+		}));
+		assert!(check(ParticleSystemSettings {
+			spin: 0.0..f32::NEG_INFINITY,
+			..Default::default()
+		}));
+	}
+
+	#[test]
+	fn resizing_the_weather_viewport_refits_the_settings() {
+		let mut weather = Weather::new(
+			Headless,
+			Precipitation::Snow,
+			graphics::Rect::new(0.0, 0.0, 800.0, 600.0),
+		);
+		weather.system_mut().settings.speed = 5.0..10.0;
+		weather.set_viewport(graphics::Rect::new(100.0, 50.0, 1600.0, 300.0));
+		let preset = Precipitation::Snow.settings(Vector2::new(1600.0, 300.0));
+		let settings = &weather.system().settings;
+		assert_eq!(settings.position, preset.position);
+		assert_eq!(
+			format!("{:?}", settings.shape),
+			format!("{:?}", preset.shape)
+		);
+		assert_eq!(settings.emission_rate, preset.emission_rate);
+		assert_eq!(settings.particle_lifetime, preset.particle_lifetime);
+		// settings that don't depend on the viewport are left alone
+		assert_eq!(settings.speed, 5.0..10.0);
+	}
+
+	#[test]
+	fn contact_normals_rotate_the_main_emitter_angle_override() {
+		let mut contact = ContactEmitter::new(
+			Headless,
+			ParticleSystemSettings {
+				spread: 0.0,
+				angle: 1.0,
+				..Default::default()
+			},
+			1,
+		);
+		let normal = Vector2::new(0.0, 1.0);
+		contact.notify_contact(Point2::new(0.0, 0.0), normal);
+		contact
+			.system_mut()
+			.emitter_overrides_mut(EmitterId::MAIN)
+			.unwrap()
+			.angle = Some(0.5);
+		contact.notify_contact(Point2::new(0.0, 0.0), normal);
+		let right_angle = std::f32::consts::PI / 2.0;
+		let directions = directions(contact.system());
+		assert!((directions[0] - (right_angle + 1.0)).abs() < 1e-4);
+		assert!((directions[1] - (right_angle + 0.5)).abs() < 1e-4);
+		// the override is put back after the burst
+		assert_eq!(
+			contact
+				.system()
+				.emitter_overrides(EmitterId::MAIN)
+				.unwrap()
+				.angle,
+			Some(0.5)
+		);
+	}
+
+	#[test]
+	fn rewinding_replays_extra_emitters_and_triggers() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 7.0,
+			..Default::default()
+		});
+		particle_system.add_emitter(Point2::new(100.0, 0.0), EmitterShape::Point);
+		let fired = Rc::new(std::cell::Cell::new(0));
+		let counter = Rc::clone(&fired);
+		particle_system.on_spawn_every_nth(3, 0.0, move |_| counter.set(counter.get() + 1));
+		let mut history = History::new(10.0);
+		for _ in 0..5 {
+			particle_system.advance(0.1);
+		}
+		history.record(&particle_system);
+		let run = |particle_system: &mut ParticleSystem<Headless>| {
+			for _ in 0..10 {
+				particle_system.advance(0.05);
+			}
+			positions(&views(particle_system))
+		};
+		let fired_before = fired.get();
+		let first_run = run(&mut particle_system);
+		let fired_first_run = fired.get() - fired_before;
+		assert!(history.rewind(&mut particle_system, 100.0) > 0.0);
+		let fired_before = fired.get();
+		assert_eq!(run(&mut particle_system), first_run);
+		assert_eq!(fired.get() - fired_before, fired_first_run);
+	}
+
+	#[test]
+	fn scrubbing_history_goes_back_and_forth() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 30.0,
+			..Default::default()
+		});
+		let mut history = History::new(10.0);
+		let mut states = vec![];
+		for _ in 0..20 {
+			particle_system.advance(0.05);
+			history.record(&particle_system);
+			states.push(positions(&views(&particle_system)));
+		}
+		let time = history.scrub_to(&mut particle_system, 0.5).unwrap();
+		assert!((time - 0.5).abs() < 1e-4);
+		assert_eq!(positions(&views(&particle_system)), states[9]);
+		assert!(history.step_back(&mut particle_system));
+		assert_eq!(positions(&views(&particle_system)), states[8]);
+		assert!(history.step_forward(&mut particle_system));
+		assert!(history.step_forward(&mut particle_system));
+		assert_eq!(positions(&views(&particle_system)), states[10]);
+		// scrubbing keeps the later states around
+		history.scrub_to(&mut particle_system, 100.0);
+		assert_eq!(positions(&views(&particle_system)), states[19]);
+		assert!(!history.step_forward(&mut particle_system));
+	}
+
+	#[test]
+	fn extra_emitters_emit_from_their_own_positions() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 20.0,
+			speed: 0.0..0.0,
+			..Default::default()
+		});
+		particle_system
+			.emitter_overrides_mut(EmitterId::MAIN)
+			.unwrap()
+			.enabled = false;
+		let left = particle_system.add_emitter(Point2::new(-100.0, 0.0), EmitterShape::Point);
+		let right = particle_system.add_emitter(Point2::new(100.0, 0.0), EmitterShape::Point);
+		particle_system
+			.emitter_overrides_mut(right)
+			.unwrap()
+			.emission_rate = Some(40.0);
+		particle_system.advance(0.5);
+		let positions = positions(&views(&particle_system));
+		let count_at = |x: f32| positions.iter().filter(|position| position.x == x).count();
+		assert_eq!(count_at(-100.0), 10);
+		assert_eq!(count_at(100.0), 20);
+		assert_eq!(positions.len(), 30);
+		assert!(particle_system.remove_emitter(left));
+		assert!(particle_system.emitter_overrides(left).is_none());
+	}
+
+	#[test]
+	fn predicted_bounds_cover_where_the_particles_go() {
+		let mut particle_system = headless_system(ParticleSystemSettings {
+			emission_rate: 0.0,
+			particle_lifetime: 2.0..2.0,
+			speed: 50.0..100.0,
+			acceleration: Vector2::new(0.0, 200.0)..Vector2::new(0.0, 200.0),
+			..Default::default()
+		});
+		particle_system.stop();
+		particle_system.emit(50);
+		let bounds = particle_system.predicted_bounds(1.0).unwrap();
+		for _ in 0..60 {
+			particle_system.advance(1.0 / 60.0);
+			for position in positions(&views(&particle_system)) {
+				assert!(
+					bounds.contains(position),
+					"{:?} is outside {:?}",
+					position,
+					bounds
+				);
+			}
+		}
+		particle_system.stop_with(StopMode::ClearParticles);
+		assert!(particle_system.predicted_bounds(1.0).is_none());
+	}
+
+	#[test]
+	fn weather_ramps_intensity_and_stays_in_the_viewport() {
+		let viewport = graphics::Rect::new(200.0, 100.0, 800.0, 600.0);
+		let mut weather = Weather::new(Headless, Precipitation::Rain, viewport);
+		weather.set_wind(Vector2::new(300.0, 0.0));
+		for _ in 0..60 {
+			weather.advance(1.0 / 60.0);
+		}
+		assert!(weather.system().count() > 0);
+		for position in positions(&views(weather.system())) {
+			assert!(
+				position.x >= viewport.x && position.x <= viewport.x + viewport.w,
+				"{:?} is outside the viewport",
+				position
+			);
+		}
+		weather.set_intensity(0.0, 1.0);
+		weather.advance(0.5);
+		assert!((weather.intensity() - 0.5).abs() < 1e-4);
+		weather.advance(1.0);
+		assert_eq!(weather.intensity(), 0.0);
+	}
+
+	#[test]
+	#[cfg(feature = "serialization")]
+	fn missing_fields_are_filled_in_with_defaults() {
+		let settings =
+			ParticleSystemSettings::from_ron("(emission_rate: 5.0, speed: (1.0, 2.0))").unwrap();
+		assert_eq!(settings.emission_rate, 5.0);
+		assert_eq!(settings.speed, 1.0..2.0);
+		let defaults = ParticleSystemSettings::default();
+		assert_eq!(settings.particle_lifetime, defaults.particle_lifetime);
+		assert!(settings.world_bounds.is_none());
+		assert!(ParticleSystemSettings::from_json("{\"mass\": [0.0, 1.0]}").is_err());
+	}
+}
@@ -0,0 +1,428 @@
+use crate::{lerp, random_from_seed, Curve, Region};
+use ggez::{
+	graphics::Color,
+	nalgebra::{Point2, Rotation2, Vector2},
+};
+use std::ops::Range;
+
+/// The stream of random numbers used to pick each particle's
+/// orbit speed. See `random_from_seed`.
+const ORBIT_STREAM: u64 = 1;
+/// The streams of random numbers used to pick each particle's
+/// pulse frequency and phase.
+const PULSE_STREAMS: (u64, u64) = (4, 5);
+/// The streams of random numbers used for the horizontal and
+/// vertical noise of `Turbulence`.
+const TURBULENCE_STREAMS: (u64, u64) = (2, 3);
+/// The stream of random numbers used to pick each particle's
+/// color from a `RandomColor` palette.
+const RANDOM_COLOR_STREAM: u64 = 6;
+
+/// Returns smoothly changing noise between -1 and 1.
+fn value_noise(seed: u64, stream: u64, x: f32) -> f32 {
+	let cell = x.floor();
+	let fraction = x - cell;
+	let get_value = |cell: f32| {
+		let seed = seed ^ (cell as i64 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+		random_from_seed(seed, stream) * 2.0 - 1.0
+	};
+	let amount = fraction * fraction * (3.0 - 2.0 * fraction);
+	lerp(get_value(cell), get_value(cell + 1.0), amount)
+}
+
+/// The parts of a particle that a `Modifier` can read and change.
+#[derive(Debug, Clone)]
+pub struct ParticleState {
+	/// The position of the particle.
+	pub position: Point2<f32>,
+	/// The velocity of the particle.
+	pub velocity: Vector2<f32>,
+	/// The angle of the particle (in radians). This has no effect
+	/// on particles that use a relative angle.
+	pub angle: f32,
+	/// A multiplier for the size of the particle. This is reset
+	/// to 1 before the modifiers run each update.
+	pub scale: f32,
+	/// A color that the particle's color is multiplied by. This is
+	/// reset to white before the modifiers run each update.
+	pub tint: Color,
+	/// How fast time passes for the particle, relative to the rest of
+	/// the particle system. This is reset to the time scale set with
+	/// `ParticleSystem::set_time_scale` (usually 1) before the modifiers
+	/// run each update, and it takes effect in the next update.
+	pub time_scale: f32,
+	pub(crate) time: f32,
+	pub(crate) lifetime: f32,
+	pub(crate) initial_speed: f32,
+	pub(crate) seed: u64,
+	pub(crate) emitter_position: Point2<f32>,
+}
+
+impl ParticleState {
+	/// Returns how far the particle is through its lifetime,
+	/// from 0 (just emitted) to 1 (about to disappear).
+	pub fn time(&self) -> f32 {
+		self.time
+	}
+
+	/// Returns how long the particle will be alive in total (in seconds).
+	pub fn lifetime(&self) -> f32 {
+		self.lifetime
+	}
+
+	/// Returns the speed the particle was emitted with.
+	pub fn initial_speed(&self) -> f32 {
+		self.initial_speed
+	}
+
+	/// Returns the particle's seed. See `random_from_seed`.
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// Returns the current position of the particle system's emitter.
+	pub fn emitter_position(&self) -> Point2<f32> {
+		self.emitter_position
+	}
+}
+
+/// Lets boxed modifiers be cloned along with their particle system.
+///
+/// This is implemented automatically for every modifier that
+/// implements `Clone`.
+pub trait CloneModifier {
+	/// Returns a boxed copy of the modifier.
+	fn clone_modifier(&self) -> Box<dyn Modifier>;
+}
+
+impl<T> CloneModifier for T
+where
+	T: Modifier + Clone + 'static,
+{
+	fn clone_modifier(&self) -> Box<dyn Modifier> {
+		Box::new(self.clone())
+	}
+}
+
+/// Changes particles every time the particle system is updated.
+///
+/// Modifiers are added to `ParticleSystem.modifiers` and run in order
+/// after each particle has moved. Implement this trait for behaviors
+/// that the particle system settings don't cover. Modifiers must
+/// implement `Clone` so particle systems can be cloned.
+pub trait Modifier: CloneModifier {
+	/// Changes a single particle. `delta_time` is the time since
+	/// the last update (in seconds).
+	fn apply(&mut self, particle: &mut ParticleState, delta_time: f32);
+}
+
+impl Clone for Box<dyn Modifier> {
+	fn clone(&self) -> Self {
+		self.clone_modifier()
+	}
+}
+
+/// Scales particles over their lifetime. The scales are evenly spaced
+/// over the lifetime of each particle and multiply the particle's size.
+#[derive(Debug, Clone)]
+pub struct ScaleOverLifetime {
+	/// The scales to use over the lifetime of each particle.
+	pub scales: Curve<f32>,
+}
+
+impl Modifier for ScaleOverLifetime {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		if let Some(scale) = self.scales.evaluate(particle.time) {
+			particle.scale *= scale;
+		}
+	}
+}
+
+/// Rotates the velocity of particles at a constant speed
+/// (in radians per second), making them move in curves.
+#[derive(Debug, Clone)]
+pub struct RotateVelocity {
+	/// How fast to rotate the velocity (in radians per second).
+	pub speed: f32,
+}
+
+impl Modifier for RotateVelocity {
+	fn apply(&mut self, particle: &mut ParticleState, delta_time: f32) {
+		particle.velocity = Rotation2::new(self.speed * delta_time) * particle.velocity;
+	}
+}
+
+/// Tints particles over their lifetime. The colors are evenly spaced
+/// over the lifetime of each particle and multiply the particle's color.
+#[derive(Debug, Clone)]
+pub struct ColorShift {
+	/// The tints to use over the lifetime of each particle.
+	pub colors: Curve<Color>,
+}
+
+impl Modifier for ColorShift {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		if let Some(color) = self.colors.evaluate(particle.time) {
+			particle.tint.r *= color.r;
+			particle.tint.g *= color.g;
+			particle.tint.b *= color.b;
+			particle.tint.a *= color.a;
+		}
+	}
+}
+
+/// How `VelocityOverLifetime` uses its keyframes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VelocityMode {
+	/// The keyframes multiply the speed each particle was emitted with.
+	Multiply,
+	/// The keyframes are used as the speed of each particle.
+	Replace,
+}
+
+/// Changes the speed of particles over their lifetime. The keyframes
+/// are evenly spaced over the lifetime of each particle. Particles
+/// keep moving in the same direction.
+///
+/// Unlike damping, this can make particles speed up again after
+/// slowing down, e.g. launching quickly, hanging in the air, and
+/// then falling.
+#[derive(Debug, Clone)]
+pub struct VelocityOverLifetime {
+	/// The speeds (or speed multipliers) to use over the lifetime
+	/// of each particle.
+	pub keyframes: Curve<f32>,
+	/// Whether the keyframes multiply or replace the particle's speed.
+	pub mode: VelocityMode,
+}
+
+impl Modifier for VelocityOverLifetime {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		let value = match self.keyframes.evaluate(particle.time) {
+			Some(value) => value,
+			None => return,
+		};
+		let target_speed = match self.mode {
+			VelocityMode::Multiply => particle.initial_speed * value,
+			VelocityMode::Replace => value,
+		};
+		let speed = particle.velocity.norm();
+		if speed > 0.0 {
+			particle.velocity *= target_speed / speed;
+		}
+	}
+}
+
+/// Moves particles in circles around the center of the emitter,
+/// even if the emitter is moving.
+#[derive(Debug, Clone)]
+pub struct Orbit {
+	/// The range of angular speeds (in radians per second) that
+	/// particles can orbit at. Each particle picks one speed.
+	pub speed: Range<f32>,
+	/// Multipliers for the orbit speed over the lifetime of each
+	/// particle. The keyframes are evenly spaced over the lifetime.
+	pub keyframes: Curve<f32>,
+}
+
+impl Modifier for Orbit {
+	fn apply(&mut self, particle: &mut ParticleState, delta_time: f32) {
+		let mut speed = lerp(
+			self.speed.start,
+			self.speed.end,
+			random_from_seed(particle.seed, ORBIT_STREAM),
+		);
+		if let Some(multiplier) = self.keyframes.evaluate(particle.time) {
+			speed *= multiplier;
+		}
+		let center = particle.emitter_position;
+		particle.position =
+			center + Rotation2::new(speed * delta_time) * (particle.position - center);
+	}
+}
+
+/// Randomly moves particles around their path. The amount of
+/// movement can change over the lifetime of each particle, e.g.
+/// so smoke starts as a tight column and spreads out as it rises.
+#[derive(Debug, Clone)]
+pub struct Turbulence {
+	/// The maximum distance particles are moved from their path.
+	pub amplitude: f32,
+	/// How quickly the movement changes direction (in changes per second).
+	pub frequency: f32,
+	/// Multipliers for the amplitude over the lifetime of each
+	/// particle. The keyframes are evenly spaced over the lifetime.
+	pub ramp: Curve<f32>,
+}
+
+impl Turbulence {
+	fn get_displacement(&self, seed: u64, age: f32, time: f32) -> Vector2<f32> {
+		let mut amplitude = self.amplitude;
+		if let Some(multiplier) = self.ramp.evaluate(time) {
+			amplitude *= multiplier;
+		}
+		let x = age * self.frequency;
+		Vector2::new(
+			value_noise(seed, TURBULENCE_STREAMS.0, x),
+			value_noise(seed, TURBULENCE_STREAMS.1, x),
+		) * amplitude
+	}
+}
+
+impl Modifier for Turbulence {
+	fn apply(&mut self, particle: &mut ParticleState, delta_time: f32) {
+		if particle.lifetime <= 0.0 {
+			return;
+		}
+		let age = particle.time * particle.lifetime;
+		let previous_age = (age - delta_time).max(0.0);
+		// move the particle by the change in displacement since the
+		// last update, so the displacement doesn't build up over time
+		particle.position += self.get_displacement(particle.seed, age, particle.time)
+			- self.get_displacement(
+				particle.seed,
+				previous_age,
+				previous_age / particle.lifetime,
+			);
+	}
+}
+
+/// Makes particles grow and shrink repeatedly, e.g. for twinkling
+/// stars. Each particle pulses at its own frequency and phase.
+#[derive(Debug, Clone)]
+pub struct Pulse {
+	/// How much the size of particles changes, as a fraction of
+	/// their size. For example, `0.5` means particles pulse between
+	/// 50% and 150% of their size.
+	pub amount: f32,
+	/// The range of frequencies (in pulses per second) particles
+	/// can pulse at.
+	pub frequency: Range<f32>,
+	/// The range of starting points (in radians) of each particle's pulse.
+	pub phase: Range<f32>,
+}
+
+impl Modifier for Pulse {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		let frequency = lerp(
+			self.frequency.start,
+			self.frequency.end,
+			random_from_seed(particle.seed, PULSE_STREAMS.0),
+		);
+		let phase = lerp(
+			self.phase.start,
+			self.phase.end,
+			random_from_seed(particle.seed, PULSE_STREAMS.1),
+		);
+		let age = particle.time * particle.lifetime;
+		let wave = (age * frequency * 2.0 * std::f32::consts::PI + phase).sin();
+		particle.scale *= 1.0 + self.amount * wave;
+	}
+}
+
+/// Changes the speed of particles depending on how far they are
+/// from the center of the emitter, e.g. so particles slow down
+/// as they reach the edge of a force field.
+#[derive(Debug, Clone)]
+pub struct SpeedByDistance {
+	/// The distance from the emitter that the last keyframe is used at.
+	pub max_distance: f32,
+	/// Multipliers for the speed each particle was emitted with.
+	/// The keyframes are evenly spaced from the center of the
+	/// emitter to `max_distance`.
+	pub keyframes: Curve<f32>,
+}
+
+impl Modifier for SpeedByDistance {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		if self.max_distance <= 0.0 {
+			return;
+		}
+		let distance = (particle.position - particle.emitter_position).norm();
+		let multiplier = match self.keyframes.evaluate(distance / self.max_distance) {
+			Some(multiplier) => multiplier,
+			None => return,
+		};
+		let target_speed = particle.initial_speed * multiplier;
+		let speed = particle.velocity.norm();
+		if speed > 0.0 {
+			particle.velocity *= target_speed / speed;
+		}
+	}
+}
+
+/// Slows down or stops time for particles inside a region, e.g. for a
+/// time-stop bubble. Particles inside the region move, age, and change
+/// more slowly, while particles outside of it aren't affected.
+#[derive(Debug, Clone)]
+pub struct FreezeRegion {
+	/// The area where time is slowed down.
+	pub region: Region,
+	/// How fast time passes inside the region, from `0.0` (stopped)
+	/// to `1.0` (normal speed).
+	pub time_scale: f32,
+}
+
+impl Modifier for FreezeRegion {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		if self.region.contains(particle.position) {
+			particle.time_scale *= self.time_scale;
+		}
+	}
+}
+
+/// Makes flat particles like confetti or leaves flutter as they fall.
+/// Each particle catches more air when its broad side faces the
+/// direction it's moving and less when it's edge-on, so spinning
+/// particles (see `ParticleSystemSettings::spin`) slow down and speed
+/// up over and over and glide from side to side.
+#[derive(Debug, Clone)]
+pub struct Flutter {
+	/// The drag on particles that are edge-on to the direction
+	/// they're moving.
+	pub min_drag: f32,
+	/// The drag on particles whose broad side faces the direction
+	/// they're moving.
+	pub max_drag: f32,
+	/// How much tilted particles glide to the side, as a fraction
+	/// of their speed per second.
+	pub lift: f32,
+}
+
+impl Modifier for Flutter {
+	fn apply(&mut self, particle: &mut ParticleState, delta_time: f32) {
+		let speed = particle.velocity.norm();
+		if speed == 0.0 {
+			return;
+		}
+		let direction = particle.velocity / speed;
+		let tilt = particle.angle - direction.y.atan2(direction.x);
+		let drag = lerp(self.min_drag, self.max_drag, tilt.sin().abs());
+		particle.velocity *= 1.0 / (1.0 + drag * delta_time);
+		let side = Vector2::new(-direction.y, direction.x);
+		particle.velocity += side * speed * self.lift * (2.0 * tilt).sin() * delta_time;
+	}
+}
+
+/// Tints each particle with a color picked at random from a palette,
+/// e.g. for confetti. The color multiplies the particle's color.
+#[derive(Debug, Clone)]
+pub struct RandomColor {
+	/// The colors to pick from.
+	pub colors: Vec<Color>,
+}
+
+impl Modifier for RandomColor {
+	fn apply(&mut self, particle: &mut ParticleState, _delta_time: f32) {
+		if self.colors.is_empty() {
+			return;
+		}
+		let index = (random_from_seed(particle.seed, RANDOM_COLOR_STREAM)
+			* self.colors.len() as f32) as usize;
+		let color = self.colors[index.min(self.colors.len() - 1)];
+		particle.tint.r *= color.r;
+		particle.tint.g *= color.g;
+		particle.tint.b *= color.b;
+		particle.tint.a *= color.a;
+	}
+}
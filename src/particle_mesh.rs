@@ -0,0 +1,152 @@
+use ggez::{
+	graphics::{self, Color, Vertex},
+	nalgebra::Point2,
+	Context, GameResult,
+};
+
+/// A mesh for drawing particles that keeps a copy of its vertices,
+/// so particle systems that use it can draw all of their particles
+/// with a single draw call (see `ParticleSystem::draw_batched`).
+///
+/// ggez meshes don't give access to their vertices, so particle
+/// systems that use a `graphics::Mesh` have to draw each particle
+/// separately. A `ParticleMesh` can be used anywhere a mesh can.
+#[derive(Debug, Clone)]
+pub struct ParticleMesh {
+	mesh: graphics::Mesh,
+	vertices: Vec<Vertex>,
+	indices: Vec<u32>,
+	bounds: graphics::Rect,
+}
+
+impl ParticleMesh {
+	/// Creates a particle mesh from a list of vertices and the
+	/// indices of the vertices that make up each triangle.
+	pub fn from_raw(
+		ctx: &mut Context,
+		vertices: Vec<Vertex>,
+		indices: Vec<u32>,
+	) -> GameResult<Self> {
+		let mesh = graphics::Mesh::from_raw(ctx, &vertices, &indices, None)?;
+		let (mut min, mut max) = (
+			Point2::new(f32::INFINITY, f32::INFINITY),
+			Point2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+		);
+		for vertex in &vertices {
+			min = Point2::new(min.x.min(vertex.pos[0]), min.y.min(vertex.pos[1]));
+			max = Point2::new(max.x.max(vertex.pos[0]), max.y.max(vertex.pos[1]));
+		}
+		let bounds = if vertices.is_empty() {
+			graphics::Rect::new(0.0, 0.0, 0.0, 0.0)
+		} else {
+			graphics::Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+		};
+		Ok(Self {
+			mesh,
+			vertices,
+			indices,
+			bounds,
+		})
+	}
+
+	/// Creates a filled rectangle.
+	pub fn rectangle(ctx: &mut Context, bounds: graphics::Rect, color: Color) -> GameResult<Self> {
+		let corners = [
+			[bounds.x, bounds.y],
+			[bounds.x + bounds.w, bounds.y],
+			[bounds.x + bounds.w, bounds.y + bounds.h],
+			[bounds.x, bounds.y + bounds.h],
+		];
+		let vertices = corners
+			.iter()
+			.map(|&pos| Vertex {
+				pos,
+				uv: [0.0, 0.0],
+				color: color.into(),
+			})
+			.collect();
+		Self::from_raw(ctx, vertices, vec![0, 1, 2, 0, 2, 3])
+	}
+
+	/// Creates a filled circle centered on the origin. `tolerance` is
+	/// the furthest the edge of the mesh can be from a perfect circle,
+	/// so smaller tolerances give smoother circles with more vertices.
+	pub fn circle(
+		ctx: &mut Context,
+		radius: f32,
+		tolerance: f32,
+		color: Color,
+	) -> GameResult<Self> {
+		// the largest angle between vertices that keeps the middle of
+		// each edge within the tolerance of the circle
+		let max_angle = 2.0 * (1.0 - (tolerance / radius.abs()).min(1.0)).acos();
+		let segments = (2.0 * std::f32::consts::PI / max_angle)
+			.ceil()
+			.clamp(3.0, 256.0) as u32;
+		let vertex = |pos| Vertex {
+			pos,
+			uv: [0.0, 0.0],
+			color: color.into(),
+		};
+		let mut vertices = vec![vertex([0.0, 0.0])];
+		let mut indices = vec![];
+		for i in 0..segments {
+			let angle = 2.0 * std::f32::consts::PI * i as f32 / segments as f32;
+			vertices.push(vertex([radius * angle.cos(), radius * angle.sin()]));
+			indices.extend_from_slice(&[0, i + 1, (i + 1) % segments + 1]);
+		}
+		Self::from_raw(ctx, vertices, indices)
+	}
+
+	/// Returns the mesh used to draw the particles one at a time.
+	pub fn mesh(&self) -> &graphics::Mesh {
+		&self.mesh
+	}
+
+	/// Adds a copy of the mesh's triangles to a list of vertices and
+	/// indices, with each vertex moved by `transform` and tinted by
+	/// `color`.
+	pub(crate) fn append_transformed<F>(
+		&self,
+		vertices: &mut Vec<Vertex>,
+		indices: &mut Vec<u32>,
+		color: Color,
+		transform: F,
+	) where
+		F: Fn(Point2<f32>) -> Point2<f32>,
+	{
+		let first_index = vertices.len() as u32;
+		vertices.extend(self.vertices.iter().map(|vertex| {
+			let position = transform(Point2::new(vertex.pos[0], vertex.pos[1]));
+			Vertex {
+				pos: [position.x, position.y],
+				uv: vertex.uv,
+				color: [
+					vertex.color[0] * color.r,
+					vertex.color[1] * color.g,
+					vertex.color[2] * color.b,
+					vertex.color[3] * color.a,
+				],
+			}
+		}));
+		indices.extend(self.indices.iter().map(|index| first_index + index));
+	}
+}
+
+impl graphics::Drawable for ParticleMesh {
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		self.mesh.draw(ctx, param)
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		Some(self.bounds)
+	}
+
+	fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		self.mesh.set_blend_mode(mode);
+	}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		self.mesh.blend_mode()
+	}
+}
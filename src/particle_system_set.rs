@@ -0,0 +1,184 @@
+use crate::{AnyParticleSystem, SubEmission};
+use ggez::{graphics, Context, GameResult};
+
+/// Identifies a particle system in a `ParticleSystemSet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct SystemHandle(usize);
+
+struct Entry {
+	system: Box<dyn AnyParticleSystem>,
+	despawn_when_finished: bool,
+	layer: i32,
+}
+
+/// A group of particle systems that are updated and drawn together.
+///
+/// Sub-emitters in a particle system can emit particles into other
+/// particle systems in the same set, so the new particles can use
+/// a different drawable (and blend mode) than their parents.
+#[derive(Default)]
+pub struct ParticleSystemSet {
+	entries: Vec<Option<Entry>>,
+	despawned: Vec<SystemHandle>,
+	/// Reused between updates so passing particles between systems
+	/// doesn't allocate.
+	sub_emissions: Vec<SubEmission>,
+}
+
+impl ParticleSystemSet {
+	/// Creates an empty set of particle systems.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a particle system to the set and returns a handle to it.
+	/// The particle system starts on layer 0.
+	pub fn add<S>(&mut self, system: S) -> SystemHandle
+	where
+		S: AnyParticleSystem + 'static,
+	{
+		self.entries.push(Some(Entry {
+			system: Box::new(system),
+			despawn_when_finished: false,
+			layer: 0,
+		}));
+		SystemHandle(self.entries.len() - 1)
+	}
+
+	/// Removes a particle system from the set and returns it.
+	pub fn remove(&mut self, handle: SystemHandle) -> Option<Box<dyn AnyParticleSystem>> {
+		self.entries
+			.get_mut(handle.0)
+			.and_then(Option::take)
+			.map(|entry| entry.system)
+	}
+
+	/// Returns the particle system with the given handle.
+	pub fn get(&self, handle: SystemHandle) -> Option<&dyn AnyParticleSystem> {
+		match self.entries.get(handle.0) {
+			Some(Some(entry)) => Some(entry.system.as_ref()),
+			_ => None,
+		}
+	}
+
+	/// Returns a mutable reference to the particle system with the given handle.
+	pub fn get_mut(&mut self, handle: SystemHandle) -> Option<&mut dyn AnyParticleSystem> {
+		match self.entries.get_mut(handle.0) {
+			Some(Some(entry)) => Some(entry.system.as_mut()),
+			_ => None,
+		}
+	}
+
+	/// Sets whether the particle system with the given handle should be
+	/// removed from the set once its emitter is stopped and all of its
+	/// particles have disappeared. The handles of removed particle
+	/// systems are returned by `drain_despawned`.
+	pub fn set_despawn_when_finished(&mut self, handle: SystemHandle, despawn: bool) {
+		if let Some(Some(entry)) = self.entries.get_mut(handle.0) {
+			entry.despawn_when_finished = despawn;
+		}
+	}
+
+	/// Sets the layer of the particle system with the given handle.
+	/// Particle systems on lower layers are drawn behind particle
+	/// systems on higher layers. Particle systems on the same layer
+	/// are drawn in the order they were added.
+	pub fn set_layer(&mut self, handle: SystemHandle, layer: i32) {
+		if let Some(Some(entry)) = self.entries.get_mut(handle.0) {
+			entry.layer = layer;
+		}
+	}
+
+	/// Returns the layer of the particle system with the given handle.
+	pub fn layer(&self, handle: SystemHandle) -> Option<i32> {
+		match self.entries.get(handle.0) {
+			Some(Some(entry)) => Some(entry.layer),
+			_ => None,
+		}
+	}
+
+	/// Sets the blend mode of the particle system with the given handle,
+	/// e.g. so additive sparks can be drawn over alpha-blended smoke.
+	/// Each particle system is drawn with its own blend mode (see
+	/// `ParticleSystem::set_blend_mode`), including when it draws to a
+	/// canvas. Particles drawn with a draw override use the blend modes
+	/// of whatever the override draws.
+	pub fn set_blend_mode(&mut self, handle: SystemHandle, mode: Option<graphics::BlendMode>) {
+		if let Some(Some(entry)) = self.entries.get_mut(handle.0) {
+			entry.system.set_blend_mode(mode);
+		}
+	}
+
+	/// Returns the blend mode of the particle system with the given handle.
+	pub fn blend_mode(&self, handle: SystemHandle) -> Option<graphics::BlendMode> {
+		match self.entries.get(handle.0) {
+			Some(Some(entry)) => entry.system.blend_mode(),
+			_ => None,
+		}
+	}
+
+	/// Returns the handles of particle systems that were removed from
+	/// the set because they finished, and clears the list.
+	pub fn drain_despawned(&mut self) -> Vec<SystemHandle> {
+		std::mem::take(&mut self.despawned)
+	}
+
+	/// Updates every particle system in the set.
+	pub fn update(&mut self, ctx: &Context) {
+		self.advance(ggez::timer::delta(ctx).as_secs_f32());
+	}
+
+	/// Updates every particle system in the set by the given number
+	/// of seconds, then passes particles from sub-emitters to the
+	/// particle systems they target.
+	pub fn advance(&mut self, delta_time: f32) {
+		for entry in self.entries.iter_mut().flatten() {
+			entry.system.advance(delta_time);
+		}
+		let mut sub_emissions = std::mem::take(&mut self.sub_emissions);
+		for entry in self.entries.iter_mut().flatten() {
+			entry.system.drain_sub_emissions(&mut sub_emissions);
+		}
+		for sub_emission in sub_emissions.drain(..) {
+			if let Some(system) = self.get_mut(sub_emission.target) {
+				system.emit_at_point(sub_emission.position, sub_emission.count);
+			}
+		}
+		self.sub_emissions = sub_emissions;
+		for (i, slot) in self.entries.iter_mut().enumerate() {
+			let finished = match slot {
+				Some(entry) => entry.despawn_when_finished && entry.system.is_finished(),
+				None => false,
+			};
+			if finished {
+				*slot = None;
+				self.despawned.push(SystemHandle(i));
+			}
+		}
+	}
+}
+
+impl graphics::Drawable for ParticleSystemSet {
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		let mut entries: Vec<&Entry> = self.entries.iter().flatten().collect();
+		entries.sort_by_key(|entry| entry.layer);
+		for entry in entries {
+			entry.system.draw(ctx, param)?;
+		}
+		Ok(())
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		None
+	}
+
+	fn set_blend_mode(&mut self, _mode: Option<graphics::BlendMode>) {}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		None
+	}
+}
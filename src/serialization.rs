@@ -0,0 +1,181 @@
+//! Saving and loading particle system settings as RON or JSON.
+//!
+//! Fields are always written in the order they're declared in, so
+//! saving the same settings twice gives the same text, and edited
+//! effect files diff cleanly. Ranges are written as a pair of the
+//! minimum and maximum values, e.g. `(10.0, 100.0)` in RON. Colors
+//! are written with `r`, `g`, `b` and `a` fields, and rectangles with
+//! `x`, `y`, `w` and `h` fields. Fields that are missing from a file
+//! are filled in with their defaults.
+
+use crate::{BoundsBehavior, Curve, ParticleSystemSettings, WellspringError};
+use ggez::graphics::{Color, Rect};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{ops::Range, sync::Arc};
+
+/// Writes a ggez `Color`, which doesn't implement the serde traits
+/// itself.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Color")]
+pub(crate) struct ColorDef {
+	r: f32,
+	g: f32,
+	b: f32,
+	a: f32,
+}
+
+/// A `Color` that can be used anywhere serde expects a type that
+/// implements the serde traits, e.g. as the keyframes of a `Curve`.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct SerdeColor(#[serde(with = "ColorDef")] Color);
+
+/// Writes a `Range` as a `(start, end)` pair.
+pub(crate) mod range {
+	use super::*;
+
+	pub fn serialize<T, S>(range: &Range<T>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		T: Serialize,
+		S: Serializer,
+	{
+		(&range.start, &range.end).serialize(serializer)
+	}
+
+	pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Range<T>, D::Error>
+	where
+		T: Deserialize<'de>,
+		D: Deserializer<'de>,
+	{
+		let (start, end) = Deserialize::deserialize(deserializer)?;
+		Ok(start..end)
+	}
+}
+
+/// Writes an optional `Range` as an optional `(start, end)` pair.
+pub(crate) mod optional_range {
+	use super::*;
+
+	pub fn serialize<S>(range: &Option<Range<f32>>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		range
+			.as_ref()
+			.map(|range| (range.start, range.end))
+			.serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Range<f32>>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let range: Option<(f32, f32)> = Deserialize::deserialize(deserializer)?;
+		Ok(range.map(|(start, end)| start..end))
+	}
+}
+
+/// Writes a ggez `Rect`, which doesn't implement the serde traits
+/// itself.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rect")]
+pub(crate) struct RectDef {
+	x: f32,
+	y: f32,
+	w: f32,
+	h: f32,
+}
+
+/// A `Rect` that can be used anywhere serde expects a type that
+/// implements the serde traits.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct SerdeRect(#[serde(with = "RectDef")] Rect);
+
+/// Writes optional world bounds as an optional `(rect, behavior)` pair.
+pub(crate) mod world_bounds {
+	use super::*;
+
+	pub fn serialize<S>(
+		bounds: &Option<(Rect, BoundsBehavior)>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		bounds
+			.as_ref()
+			.map(|(rect, behavior)| (SerdeRect(*rect), behavior))
+			.serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<(Rect, BoundsBehavior)>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let bounds: Option<(SerdeRect, BoundsBehavior)> = Deserialize::deserialize(deserializer)?;
+		Ok(bounds.map(|(rect, behavior)| (rect.0, behavior)))
+	}
+}
+
+/// Writes a shared `Curve` of colors.
+pub(crate) mod color_curve {
+	use super::*;
+
+	pub fn serialize<S>(curve: &Arc<Curve<Color>>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		Curve {
+			keyframes: curve.keyframes.iter().copied().map(SerdeColor).collect(),
+			easing: curve.easing,
+			segment_easing: curve.segment_easing.clone(),
+		}
+		.serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Arc<Curve<Color>>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let curve: Curve<SerdeColor> = Deserialize::deserialize(deserializer)?;
+		Ok(Arc::new(Curve {
+			keyframes: curve.keyframes.into_iter().map(|color| color.0).collect(),
+			easing: curve.easing,
+			segment_easing: curve.segment_easing,
+		}))
+	}
+}
+
+impl ParticleSystemSettings {
+	/// Returns the settings as nicely formatted RON.
+	pub fn to_ron_pretty(&self) -> Result<String, WellspringError> {
+		// use the same line endings on every platform so files
+		// saved on different computers don't differ
+		let config = ron::ser::PrettyConfig::new()
+			.with_new_line("\n".to_string())
+			.with_decimal_floats(true);
+		Ok(ron::ser::to_string_pretty(self, config)?)
+	}
+
+	/// Returns the settings as nicely formatted JSON.
+	pub fn to_json_pretty(&self) -> Result<String, WellspringError> {
+		Ok(serde_json::to_string_pretty(self)?)
+	}
+
+	/// Loads settings from RON. The settings are checked with
+	/// `validate` after they're loaded.
+	pub fn from_ron(ron: &str) -> Result<Self, WellspringError> {
+		let settings: Self = ron::de::from_str(ron)?;
+		settings.validate()?;
+		Ok(settings)
+	}
+
+	/// Loads settings from JSON. The settings are checked with
+	/// `validate` after they're loaded.
+	pub fn from_json(json: &str) -> Result<Self, WellspringError> {
+		let settings: Self = serde_json::from_str(json)?;
+		settings.validate()?;
+		Ok(settings)
+	}
+}
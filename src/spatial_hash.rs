@@ -0,0 +1,125 @@
+use ggez::{graphics::Rect, nalgebra::Point2};
+use std::collections::HashMap;
+
+/// Groups points into square cells so points in an area
+/// can be found without checking every point.
+#[derive(Clone)]
+pub(crate) struct SpatialHash {
+	cell_size: f32,
+	cells: HashMap<(i32, i32), Vec<usize>>,
+	/// Lists from cells that were emptied, kept so new cells don't
+	/// need to allocate.
+	spare_cells: Vec<Vec<usize>>,
+}
+
+impl SpatialHash {
+	pub fn new(cell_size: f32) -> Self {
+		Self {
+			cell_size,
+			cells: HashMap::new(),
+			spare_cells: vec![],
+		}
+	}
+
+	pub fn cell_size(&self) -> f32 {
+		self.cell_size
+	}
+
+	fn get_cell(&self, x: f32, y: f32) -> (i32, i32) {
+		(
+			(x / self.cell_size).floor() as i32,
+			(y / self.cell_size).floor() as i32,
+		)
+	}
+
+	/// Replaces the contents of the spatial hash with the given points.
+	/// Each point is stored as its index in the iterator.
+	pub fn rebuild(&mut self, points: impl Iterator<Item = Point2<f32>>) {
+		// every cell is removed, so cells the points have moved away
+		// from don't pile up, but their lists are reused
+		for (_, mut cell) in self.cells.drain() {
+			cell.clear();
+			self.spare_cells.push(cell);
+		}
+		for (i, point) in points.enumerate() {
+			let key = self.get_cell(point.x, point.y);
+			let spare_cells = &mut self.spare_cells;
+			self.cells
+				.entry(key)
+				.or_insert_with(|| spare_cells.pop().unwrap_or_default())
+				.push(i);
+		}
+	}
+
+	/// Returns the indices of all the points in cells that overlap
+	/// the given rectangle. Some of the points may be outside of
+	/// the rectangle.
+	pub fn query(&self, rect: Rect) -> Vec<usize> {
+		let mut indices = vec![];
+		self.query_into(rect, &mut indices);
+		indices
+	}
+
+	/// Like `query`, but replaces the contents of an existing `Vec`
+	/// instead of allocating a new one.
+	pub fn query_into(&self, rect: Rect, indices: &mut Vec<usize>) {
+		indices.clear();
+		let (min_x, min_y) = self.get_cell(rect.x, rect.y);
+		let (max_x, max_y) = self.get_cell(rect.x + rect.w, rect.y + rect.h);
+		for x in min_x..=max_x {
+			for y in min_y..=max_y {
+				if let Some(cell) = self.cells.get(&(x, y)) {
+					indices.extend_from_slice(cell);
+				}
+			}
+		}
+	}
+}
+
+/// Counts how many points are in each square cell.
+#[derive(Clone)]
+pub(crate) struct OccupancyGrid {
+	cell_size: f32,
+	counts: HashMap<(i32, i32), usize>,
+}
+
+impl OccupancyGrid {
+	pub fn new(cell_size: f32) -> Self {
+		Self {
+			cell_size,
+			counts: HashMap::new(),
+		}
+	}
+
+	pub fn cell_size(&self) -> f32 {
+		self.cell_size
+	}
+
+	fn get_cell(&self, point: Point2<f32>) -> (i32, i32) {
+		(
+			(point.x / self.cell_size).floor() as i32,
+			(point.y / self.cell_size).floor() as i32,
+		)
+	}
+
+	/// Replaces the contents of the grid with the given points.
+	pub fn rebuild(&mut self, points: impl Iterator<Item = Point2<f32>>) {
+		// clearing the map keeps its memory, so it doesn't need to
+		// allocate again unless more cells are used than before
+		self.counts.clear();
+		for point in points {
+			self.add(point);
+		}
+	}
+
+	/// Adds a point to the cell it's in.
+	pub fn add(&mut self, point: Point2<f32>) {
+		let cell = self.get_cell(point);
+		*self.counts.entry(cell).or_default() += 1;
+	}
+
+	/// Returns the number of points in the same cell as the given point.
+	pub fn count(&self, point: Point2<f32>) -> usize {
+		self.counts.get(&self.get_cell(point)).copied().unwrap_or(0)
+	}
+}
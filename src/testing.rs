@@ -0,0 +1,189 @@
+//! Helpers for snapshot testing particle effects.
+//!
+//! A `Snapshot` is a compact summary of the particles in a system
+//! after it's been running for a while. Since seeded particle systems
+//! always behave the same way, snapshots can be saved and compared
+//! later to catch unexpected changes to an effect.
+//!
+//! # Example
+//! ```
+//! # use wellspring::{testing, ParticleSystemSettings};
+//! let settings = ParticleSystemSettings {
+//!     emission_rate: 100.0,
+//!     ..Default::default()
+//! };
+//! let snapshot = testing::snapshot(settings.clone(), 1234, 1.0 / 60.0, 120);
+//! // the same seed always gives the same particles, so `snapshot.to_string()`
+//! // can be saved to a file and compared against later
+//! assert_eq!(snapshot, testing::snapshot(settings, 1234, 1.0 / 60.0, 120));
+//! ```
+//!
+//! With the `allocation_checks` feature, particle systems panic if an
+//! update allocates memory once they've warmed up. Allocations are
+//! only counted if `CountingAllocator` is registered as the global
+//! allocator, which is up to the application (or test) using it:
+//! ```
+//! # #[cfg(feature = "allocation_checks")]
+//! #[global_allocator]
+//! static ALLOCATOR: wellspring::testing::CountingAllocator =
+//!     wellspring::testing::CountingAllocator;
+//! # fn main() {}
+//! ```
+//!
+//! Reserve space for the particles up front so the buffers don't
+//! have to grow later:
+//! ```
+//! # fn run(particle_system: &mut wellspring::ParticleSystem<ggez::graphics::Mesh>) {
+//! particle_system.reserve(1000);
+//! for _ in 0..600 {
+//!     // panics if an update allocates after the first few seconds
+//!     particle_system.advance(1.0 / 60.0);
+//! }
+//! # }
+//! ```
+//! `assert_no_allocations` can also be used to check other code.
+
+use crate::{simulate, ParticleSystemSettings, ParticleView};
+use std::fmt;
+#[cfg(feature = "allocation_checks")]
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	cell::Cell,
+};
+
+#[cfg(feature = "allocation_checks")]
+thread_local! {
+	/// The number of times memory has been allocated on this thread.
+	static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A compact summary of a single particle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParticleSnapshot {
+	/// The position of the particle, rounded to the nearest pixel.
+	pub position: (i32, i32),
+	/// The color of the particle as 8-bit RGBA values.
+	pub color: (u8, u8, u8, u8),
+}
+
+impl From<&ParticleView> for ParticleSnapshot {
+	fn from(view: &ParticleView) -> Self {
+		Self {
+			position: (
+				view.position.x.round() as i32,
+				view.position.y.round() as i32,
+			),
+			color: view.color.to_rgba(),
+		}
+	}
+}
+
+/// A compact summary of the particles in a particle system.
+///
+/// Snapshots can be compared directly, or turned into text
+/// (one line per particle) with `to_string()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+	/// The particles, from oldest to newest.
+	pub particles: Vec<ParticleSnapshot>,
+}
+
+impl Snapshot {
+	/// Creates a snapshot from a list of particles.
+	pub fn new(particles: &[ParticleView]) -> Self {
+		Self {
+			particles: particles.iter().map(ParticleSnapshot::from).collect(),
+		}
+	}
+
+	/// Returns the number of particles in the snapshot.
+	pub fn count(&self) -> usize {
+		self.particles.len()
+	}
+}
+
+impl fmt::Display for Snapshot {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "count: {}", self.count())?;
+		for particle in &self.particles {
+			let (x, y) = particle.position;
+			let (r, g, b, a) = particle.color;
+			writeln!(f, "{} {} #{:02x}{:02x}{:02x}{:02x}", x, y, r, g, b, a)?;
+		}
+		Ok(())
+	}
+}
+
+/// Runs a particle system with the given settings and seed for
+/// the given number of frames and returns a snapshot of the
+/// particles. See `simulate`.
+pub fn snapshot(
+	settings: ParticleSystemSettings,
+	seed: u64,
+	delta_time: f32,
+	frames: usize,
+) -> Snapshot {
+	Snapshot::new(&simulate(settings, seed, delta_time, frames))
+}
+
+/// A global allocator that counts how many times memory is
+/// allocated on each thread, so allocations on other threads don't
+/// affect the checks. Memory is allocated by the system allocator.
+///
+/// Wellspring doesn't install this itself; register it with
+/// `#[global_allocator]` to enable the allocation checks. Without
+/// it, `allocation_count` always returns 0 and the checks never fail.
+#[cfg(feature = "allocation_checks")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountingAllocator;
+
+#[cfg(feature = "allocation_checks")]
+impl CountingAllocator {
+	fn count_allocation() {
+		// the count can't be reached while the thread is shutting
+		// down, but those allocations don't matter anyway
+		let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+	}
+}
+
+#[cfg(feature = "allocation_checks")]
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		Self::count_allocation();
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		Self::count_allocation();
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+/// Returns the number of times memory has been allocated on the
+/// current thread by `CountingAllocator`.
+#[cfg(feature = "allocation_checks")]
+pub fn allocation_count() -> usize {
+	ALLOCATIONS.try_with(Cell::get).unwrap_or(0)
+}
+
+/// Runs a function and panics if it allocated any memory on the
+/// current thread.
+#[cfg(feature = "allocation_checks")]
+pub fn assert_no_allocations<T, F>(f: F) -> T
+where
+	F: FnOnce() -> T,
+{
+	let before = allocation_count();
+	let result = f();
+	let allocations = allocation_count() - before;
+	assert!(
+		allocations == 0,
+		"expected no allocations, but memory was allocated {} times",
+		allocations
+	);
+	result
+}
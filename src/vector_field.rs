@@ -0,0 +1,79 @@
+use ggez::nalgebra::{Point2, Vector2};
+
+/// A grid of force vectors that particles move through, like
+/// a river current or the airflow from a fan.
+///
+/// Forces between the points of the grid are blended smoothly.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct VectorField {
+	/// The number of points along the x axis of the grid.
+	pub columns: usize,
+	/// The number of points along the y axis of the grid.
+	pub rows: usize,
+	/// The force at each point of the grid, row by row.
+	pub vectors: Vec<Vector2<f32>>,
+	/// The position of the top-left point of the grid.
+	pub position: Point2<f32>,
+	/// The distance between points of the grid.
+	pub spacing: f32,
+	/// How fast the grid moves (in pixels per second).
+	pub scroll_velocity: Vector2<f32>,
+	/// Whether the grid repeats infinitely. If `false`, there's
+	/// no force outside of the grid.
+	pub repeat: bool,
+}
+
+impl VectorField {
+	/// Creates a new vector field from a grid of vectors (listed
+	/// row by row) with the given number of columns.
+	pub fn new(columns: usize, spacing: f32, vectors: Vec<Vector2<f32>>) -> Self {
+		let rows = vectors.len().checked_div(columns).unwrap_or(0);
+		Self {
+			columns,
+			rows,
+			vectors,
+			position: Point2::new(0.0, 0.0),
+			spacing,
+			scroll_velocity: Vector2::new(0.0, 0.0),
+			repeat: false,
+		}
+	}
+
+	fn get_vector(&self, column: i64, row: i64) -> Vector2<f32> {
+		let (columns, rows) = (self.columns as i64, self.rows as i64);
+		let (column, row) = if self.repeat {
+			(column.rem_euclid(columns), row.rem_euclid(rows))
+		} else if column < 0 || column >= columns || row < 0 || row >= rows {
+			return Vector2::new(0.0, 0.0);
+		} else {
+			(column, row)
+		};
+		self.vectors
+			.get((row * columns + column) as usize)
+			.copied()
+			.unwrap_or_else(|| Vector2::new(0.0, 0.0))
+	}
+
+	/// Returns the force at a point after the field has been
+	/// scrolling for `time` seconds.
+	pub fn sample(&self, point: Point2<f32>, time: f32) -> Vector2<f32> {
+		if self.columns == 0 || self.rows == 0 || self.spacing <= 0.0 {
+			return Vector2::new(0.0, 0.0);
+		}
+		let grid_position = (point - self.position - self.scroll_velocity * time) / self.spacing;
+		let column = grid_position.x.floor();
+		let row = grid_position.y.floor();
+		let fraction_x = grid_position.x - column;
+		let fraction_y = grid_position.y - row;
+		let (column, row) = (column as i64, row as i64);
+		let top = self.get_vector(column, row) * (1.0 - fraction_x)
+			+ self.get_vector(column + 1, row) * fraction_x;
+		let bottom = self.get_vector(column, row + 1) * (1.0 - fraction_x)
+			+ self.get_vector(column + 1, row + 1) * fraction_x;
+		top * (1.0 - fraction_y) + bottom * fraction_y
+	}
+}
@@ -0,0 +1,293 @@
+use crate::{
+	BrownianMotion, Collider, ColliderShape, DrawableProvider, EmitterShape, ParticleSystem,
+	ParticleSystemSettings, ParticleView, StrengthResponse, WindSettings,
+};
+use ggez::{
+	graphics::{self, Color},
+	nalgebra::{Point2, Vector2},
+	Context, GameResult,
+};
+
+/// A kind of weather that falls from the sky.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serialization",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Precipitation {
+	/// Fast, thin drops that point in the direction they're falling.
+	Rain,
+	/// Slow, light flakes that drift around and are easily blown
+	/// by the wind.
+	Snow,
+	/// Heavy stones that fall quickly and bounce off the ground.
+	Hail,
+}
+
+impl Precipitation {
+	/// Returns settings for this kind of weather covering a viewport
+	/// of the given size. The particle system is screen anchored, so
+	/// it should be updated with the camera (see
+	/// `ParticleSystem::update_with_camera`).
+	pub fn settings(self, viewport_size: Vector2<f32>) -> ParticleSystemSettings {
+		let settings = ParticleSystemSettings {
+			screen_anchored: true,
+			angle: std::f32::consts::PI / 2.0,
+			strength_response: StrengthResponse {
+				emission_rate: vec![0.0, 1.0].into(),
+				..Default::default()
+			},
+			..Default::default()
+		};
+		let mut settings = match self {
+			Precipitation::Rain => ParticleSystemSettings {
+				speed: 500.0..700.0,
+				spread: 0.05,
+				acceleration: Vector2::new(0.0, 300.0)..Vector2::new(0.0, 300.0),
+				use_relative_angle: true,
+				colors: vec![Color::new(0.7, 0.8, 1.0, 0.6)].into(),
+				max_bounces: Some(0),
+				..settings
+			},
+			Precipitation::Snow => ParticleSystemSettings {
+				speed: 25.0..50.0,
+				spread: 0.5,
+				acceleration: Vector2::new(0.0, 20.0)..Vector2::new(0.0, 20.0),
+				damping: 0.5..1.0,
+				mass: 0.25..0.5,
+				brownian_motion: Some(BrownianMotion {
+					strength: 4.0,
+					frequency: 4.0,
+				}),
+				spin: -1.0..1.0,
+				max_bounces: Some(0),
+				..settings
+			},
+			Precipitation::Hail => ParticleSystemSettings {
+				speed: 300.0..450.0,
+				spread: 0.1,
+				acceleration: Vector2::new(0.0, 600.0)..Vector2::new(0.0, 600.0),
+				mass: 2.0..4.0,
+				spin: -6.0..6.0,
+				colors: vec![Color::new(0.9, 0.95, 1.0, 1.0)].into(),
+				max_bounces: Some(2),
+				..settings
+			},
+		};
+		self.fit_to_viewport(&mut settings, viewport_size);
+		settings
+	}
+
+	/// Sets the settings that depend on the size of the viewport:
+	/// the position, shape, emission rate and particle lifetime.
+	fn fit_to_viewport(self, settings: &mut ParticleSystemSettings, viewport_size: Vector2<f32>) {
+		let (width, height) = (viewport_size.x.max(1.0), viewport_size.y.max(1.0));
+		// particles are spawned along the top of the viewport, exactly
+		// as wide as it. Screen anchored particles wrap around at the
+		// edges of the viewport, so sideways winds don't leave gaps,
+		// and a wider line would just fold extra particles back in
+		// near the edges.
+		settings.position = Point2::new(width / 2.0, 0.0);
+		settings.shape = EmitterShape::Rectangle(Vector2::new(width, 0.0), 0.0);
+		// wider viewports need more particles to look as dense, and
+		// taller ones need particles to live long enough to fall
+		// all the way down
+		let (particles_per_pixel, fastest_fall, slowest_fall) = match self {
+			Precipitation::Rain => (0.5, 700.0, 500.0),
+			Precipitation::Snow => (0.05, 40.0, 25.0),
+			Precipitation::Hail => (0.1, 450.0, 300.0),
+		};
+		settings.emission_rate = width * particles_per_pixel;
+		settings.particle_lifetime = height / fastest_fall..height / slowest_fall;
+	}
+
+	/// Returns how bouncy the ground is for this kind of weather.
+	fn ground_restitution(self) -> f32 {
+		match self {
+			Precipitation::Hail => 0.3,
+			Precipitation::Rain | Precipitation::Snow => 0.0,
+		}
+	}
+}
+
+/// A rain, snow, or hail effect that covers the screen, built on
+/// top of a `ParticleSystem`.
+///
+/// The intensity of the weather can be ramped up and down over time
+/// (see `set_intensity`), and a ground line can be set so games can
+/// spawn splashes or pile up snow when particles land (see
+/// `on_ground_hit`).
+pub struct Weather<D>
+where
+	D: DrawableProvider,
+{
+	system: ParticleSystem<D>,
+	precipitation: Precipitation,
+	viewport: graphics::Rect,
+	intensity: f32,
+	target_intensity: f32,
+	ramp_speed: f32,
+}
+
+impl<D> Weather<D>
+where
+	D: DrawableProvider,
+{
+	/// Creates a new weather effect that covers the given viewport
+	/// (the area of the world the camera can see). The weather starts
+	/// at full intensity.
+	pub fn new(drawable: D, precipitation: Precipitation, viewport: graphics::Rect) -> Self {
+		let settings = precipitation.settings(Vector2::new(viewport.w, viewport.h));
+		Self {
+			system: ParticleSystem::new(drawable, settings),
+			precipitation,
+			viewport,
+			intensity: 1.0,
+			target_intensity: 1.0,
+			ramp_speed: 0.0,
+		}
+	}
+
+	/// Returns the particle system that draws the weather.
+	pub fn system(&self) -> &ParticleSystem<D> {
+		&self.system
+	}
+
+	/// Returns a mutable reference to the particle system that draws
+	/// the weather, e.g. for changing its settings.
+	pub fn system_mut(&mut self) -> &mut ParticleSystem<D> {
+		&mut self.system
+	}
+
+	/// Returns the kind of weather.
+	pub fn precipitation(&self) -> Precipitation {
+		self.precipitation
+	}
+
+	/// Returns the area of the world the weather covers.
+	pub fn viewport(&self) -> graphics::Rect {
+		self.viewport
+	}
+
+	/// Sets the area of the world the weather covers, e.g. when the
+	/// camera moves or the window is resized.
+	///
+	/// If the size of the viewport changes, the settings that depend
+	/// on it (the position, shape, emission rate and particle lifetime)
+	/// are recalculated, replacing any changes made to them with
+	/// `system_mut`. Particles that are already falling keep their
+	/// old lifetimes.
+	pub fn set_viewport(&mut self, viewport: graphics::Rect) {
+		if viewport.w != self.viewport.w || viewport.h != self.viewport.h {
+			self.precipitation.fit_to_viewport(
+				&mut self.system.settings,
+				Vector2::new(viewport.w, viewport.h),
+			);
+		}
+		self.viewport = viewport;
+	}
+
+	/// Returns the current intensity of the weather, from 0 (clear
+	/// skies) to 1 (a full storm).
+	pub fn intensity(&self) -> f32 {
+		self.intensity
+	}
+
+	/// Changes the intensity of the weather, from 0 (clear skies) to
+	/// 1 (a full storm), over `ramp_time` seconds. Particles that are
+	/// already falling are left alone, so the weather clears up
+	/// gradually even if `ramp_time` is 0.
+	pub fn set_intensity(&mut self, intensity: f32, ramp_time: f32) {
+		self.target_intensity = intensity.clamp(0.0, 1.0);
+		if ramp_time > 0.0 {
+			self.ramp_speed = (self.target_intensity - self.intensity).abs() / ramp_time;
+		} else {
+			self.intensity = self.target_intensity;
+			self.system.set_strength(self.intensity);
+		}
+	}
+
+	/// Sets the wind blowing the particles, or removes it if `wind`
+	/// is zero. Lighter particles like snow are blown around more.
+	pub fn set_wind(&mut self, wind: Vector2<f32>) {
+		self.system.settings.wind = if wind.norm() == 0.0 {
+			None
+		} else {
+			Some(WindSettings {
+				base: wind,
+				gust_strength: 0.3,
+				gust_frequency: 0.2,
+			})
+		};
+	}
+
+	/// Sets the height (the y coordinate in the world) of the ground
+	/// the particles land on, or removes the ground if `ground` is `None`.
+	/// Rain and snow disappear when they land, and hail bounces.
+	pub fn set_ground(&mut self, ground: Option<f32>) {
+		self.system.settings.colliders = match ground {
+			Some(y) => vec![Collider {
+				shape: ColliderShape::Plane {
+					point: Point2::new(0.0, y),
+					normal: Vector2::new(0.0, -1.0),
+				},
+				restitution: self.precipitation.ground_restitution(),
+				friction: 0.5,
+			}],
+			None => vec![],
+		};
+	}
+
+	/// Calls a function when a particle first lands on the ground
+	/// (see `set_ground`), e.g. to spawn a splash or pile up snow.
+	/// The function is called with the particle at the point it landed.
+	/// Hail that bounces only counts as landing the first time it
+	/// hits the ground.
+	pub fn on_ground_hit<F>(&mut self, callback: F)
+	where
+		F: Fn(ParticleView) + 'static,
+	{
+		self.system.on_first_collision(0.0, callback);
+	}
+
+	/// Updates the weather.
+	pub fn update(&mut self, ctx: &Context) {
+		self.advance(ggez::timer::delta(ctx).as_secs_f32());
+	}
+
+	/// Updates the weather by the given number of seconds.
+	pub fn advance(&mut self, delta_time: f32) {
+		if self.intensity != self.target_intensity {
+			let step = self.ramp_speed * delta_time;
+			self.intensity = if self.intensity < self.target_intensity {
+				(self.intensity + step).min(self.target_intensity)
+			} else {
+				(self.intensity - step).max(self.target_intensity)
+			};
+			self.system.set_strength(self.intensity);
+		}
+		self.system.emitter.set_camera(Some(self.viewport));
+		self.system.advance(delta_time);
+	}
+}
+
+impl<D> graphics::Drawable for Weather<D>
+where
+	D: DrawableProvider,
+{
+	fn draw(&self, ctx: &mut Context, param: graphics::DrawParam) -> GameResult {
+		graphics::Drawable::draw(&self.system, ctx, param)
+	}
+
+	fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
+		Some(self.viewport)
+	}
+
+	fn set_blend_mode(&mut self, mode: Option<graphics::BlendMode>) {
+		graphics::Drawable::set_blend_mode(&mut self.system, mode);
+	}
+
+	fn blend_mode(&self) -> Option<graphics::BlendMode> {
+		graphics::Drawable::blend_mode(&self.system)
+	}
+}